@@ -1,17 +1,11 @@
-#[macro_use]
-extern crate ndarray;
 extern crate simplecv;
 
-use ndarray::prelude::*;
 use simplecv::io::*;
-use simplecv::color::*;
 use simplecv::filter::*;
+use simplecv::utils::map_channels;
 
 fn main() {
     let lenna = imread("lenna.png");
-    let mut buffer = Array::zeros((512, 512, 3));
-    for c in 0..3 {
-        gaussian_smooth_(&lenna.slice(s![..,..,c as usize]), 7, BorderType::Reflect, &mut buffer.slice_mut(s![..,..,c as usize]));
-    }
+    let buffer = map_channels(&lenna, |channel| gaussian_smooth(channel, 7, BorderType::Reflect));
     imsave(&buffer, "blur.png");
 }
@@ -0,0 +1,171 @@
+//! Pixel-wise arithmetic operations on images.
+//!
+//! The operands must have the same shape. Results of `add`, `subtract`,
+//! `multiply` and `divide` are clipped to \[0.0, 1.0\].
+use ndarray::prelude::*;
+use ndarray::{Data, Dimension, Zip};
+
+/// Apply `f` on each pair of pixels of two images of the same shape.
+fn zip_map<S, T, D, F>(a: &ArrayBase<S, D>, b: &ArrayBase<T, D>, f: F) -> Array<f64, D>
+    where S: Data<Elem=f64>, T: Data<Elem=f64>, D: Dimension, F: Fn(f64, f64) -> f64
+{
+    assert_eq!(a.shape(), b.shape(), "Images must have the same shape.");
+    let mut out = Array::zeros(a.raw_dim());
+    Zip::from(&mut out).and(a).and(b).apply(|o, &x, &y| *o = f(x, y));
+    out
+}
+
+/// Add two images.
+/// # Example:
+/// ```
+/// let a = ndarray::arr2(&[[0.2, 0.7]]);
+/// let b = ndarray::arr2(&[[0.3, 0.6]]);
+/// let sum = simplecv::arithmetic::add(&a, &b);
+/// assert!((sum[[0, 0]] - 0.5).abs() < 1e-12);
+/// // overflow is clipped
+/// assert_eq!(sum[[0, 1]], 1.0);
+/// ```
+pub fn add<S, T, D>(a: &ArrayBase<S, D>, b: &ArrayBase<T, D>) -> Array<f64, D>
+    where S: Data<Elem=f64>, T: Data<Elem=f64>, D: Dimension
+{
+    zip_map(a, b, |x, y| (x + y).clamp(0.0, 1.0))
+}
+
+/// Subtract `b` from `a`.
+/// # Example:
+/// ```
+/// let a = ndarray::arr2(&[[0.7, 0.2]]);
+/// let b = ndarray::arr2(&[[0.2, 0.6]]);
+/// let diff = simplecv::arithmetic::subtract(&a, &b);
+/// assert!((diff[[0, 0]] - 0.5).abs() < 1e-12);
+/// // underflow is clipped
+/// assert_eq!(diff[[0, 1]], 0.0);
+/// ```
+pub fn subtract<S, T, D>(a: &ArrayBase<S, D>, b: &ArrayBase<T, D>) -> Array<f64, D>
+    where S: Data<Elem=f64>, T: Data<Elem=f64>, D: Dimension
+{
+    zip_map(a, b, |x, y| (x - y).clamp(0.0, 1.0))
+}
+
+/// Multiply two images pixel by pixel.
+/// # Example:
+/// ```
+/// let a = ndarray::arr2(&[[0.5, 0.8]]);
+/// let b = ndarray::arr2(&[[0.5, 2.0]]);
+/// let product = simplecv::arithmetic::multiply(&a, &b);
+/// assert_eq!(product[[0, 0]], 0.25);
+/// assert_eq!(product[[0, 1]], 1.0);
+/// ```
+pub fn multiply<S, T, D>(a: &ArrayBase<S, D>, b: &ArrayBase<T, D>) -> Array<f64, D>
+    where S: Data<Elem=f64>, T: Data<Elem=f64>, D: Dimension
+{
+    zip_map(a, b, |x, y| (x * y).clamp(0.0, 1.0))
+}
+
+/// Divide `a` by `b` pixel by pixel.
+///
+/// Pixels where `b` is zero are set to 0.0.
+/// # Example:
+/// ```
+/// let a = ndarray::arr2(&[[0.25, 0.8, 0.6]]);
+/// let b = ndarray::arr2(&[[0.5, 0.4, 0.0]]);
+/// let quotient = simplecv::arithmetic::divide(&a, &b);
+/// assert_eq!(quotient, ndarray::arr2(&[[0.5, 1.0, 0.0]]));
+/// ```
+pub fn divide<S, T, D>(a: &ArrayBase<S, D>, b: &ArrayBase<T, D>) -> Array<f64, D>
+    where S: Data<Elem=f64>, T: Data<Elem=f64>, D: Dimension
+{
+    zip_map(a, b, |x, y| if y == 0.0 { 0.0 } else { (x / y).clamp(0.0, 1.0) })
+}
+
+/// Compute the absolute difference of two images.
+/// # Example:
+/// ```
+/// let a = ndarray::arr2(&[[0.25, 0.75]]);
+/// let b = ndarray::arr2(&[[0.75, 0.5]]);
+/// let diff = simplecv::arithmetic::abs_diff(&a, &b);
+/// assert_eq!(diff, ndarray::arr2(&[[0.5, 0.25]]));
+/// ```
+pub fn abs_diff<S, T, D>(a: &ArrayBase<S, D>, b: &ArrayBase<T, D>) -> Array<f64, D>
+    where S: Data<Elem=f64>, T: Data<Elem=f64>, D: Dimension
+{
+    zip_map(a, b, |x, y| (x - y).abs())
+}
+
+/// Compute the weighted sum `alpha * a + beta * b + gamma`.
+///
+/// It is the same as OpenCV `addWeighted()`. The result is not clipped.
+/// # Example:
+/// ```
+/// let a = ndarray::arr3(&[[[0.2, 0.4, 0.6]]]);
+/// let b = ndarray::arr3(&[[[0.8, 0.0, 0.5]]]);
+/// let blended = simplecv::arithmetic::add_weighted(&a, 0.7, &b, 0.3, 0.1);
+/// let expected = &a * 0.7 + &b * 0.3 + 0.1;
+/// assert!(simplecv::utils::max_diff(&blended, &expected) < 1e-12);
+/// ```
+pub fn add_weighted<S, T, D>(a: &ArrayBase<S, D>, alpha: f64, b: &ArrayBase<T, D>, beta: f64, gamma: f64) -> Array<f64, D>
+    where S: Data<Elem=f64>, T: Data<Elem=f64>, D: Dimension
+{
+    zip_map(a, b, |x, y| alpha * x + beta * y + gamma)
+}
+
+/// Apply `f` on each pair of pixels of two masks of the same shape.
+fn zip_map_mask<S, T, F>(a: &ArrayBase<S, Ix2>, b: &ArrayBase<T, Ix2>, f: F) -> Array<bool, Ix2>
+    where S: Data<Elem=bool>, T: Data<Elem=bool>, F: Fn(bool, bool) -> bool
+{
+    assert_eq!(a.shape(), b.shape(), "Masks must have the same shape.");
+    let mut out = Array::from_elem(a.raw_dim(), false);
+    Zip::from(&mut out).and(a).and(b).apply(|o, &x, &y| *o = f(x, y));
+    out
+}
+
+/// Compute the pixel-wise AND of two masks.
+/// # Example:
+/// ```
+/// let a = ndarray::arr2(&[[true, true], [false, false]]);
+/// let b = ndarray::arr2(&[[true, false], [true, false]]);
+/// assert_eq!(simplecv::arithmetic::bitwise_and(&a, &b), ndarray::arr2(&[[true, false], [false, false]]));
+/// ```
+pub fn bitwise_and<S, T>(a: &ArrayBase<S, Ix2>, b: &ArrayBase<T, Ix2>) -> Array<bool, Ix2>
+    where S: Data<Elem=bool>, T: Data<Elem=bool>
+{
+    zip_map_mask(a, b, |x, y| x && y)
+}
+
+/// Compute the pixel-wise OR of two masks.
+/// # Example:
+/// ```
+/// let a = ndarray::arr2(&[[true, true], [false, false]]);
+/// let b = ndarray::arr2(&[[true, false], [true, false]]);
+/// assert_eq!(simplecv::arithmetic::bitwise_or(&a, &b), ndarray::arr2(&[[true, true], [true, false]]));
+/// ```
+pub fn bitwise_or<S, T>(a: &ArrayBase<S, Ix2>, b: &ArrayBase<T, Ix2>) -> Array<bool, Ix2>
+    where S: Data<Elem=bool>, T: Data<Elem=bool>
+{
+    zip_map_mask(a, b, |x, y| x || y)
+}
+
+/// Compute the pixel-wise XOR of two masks.
+/// # Example:
+/// ```
+/// let a = ndarray::arr2(&[[true, true], [false, false]]);
+/// let b = ndarray::arr2(&[[true, false], [true, false]]);
+/// assert_eq!(simplecv::arithmetic::bitwise_xor(&a, &b), ndarray::arr2(&[[false, true], [true, false]]));
+/// ```
+pub fn bitwise_xor<S, T>(a: &ArrayBase<S, Ix2>, b: &ArrayBase<T, Ix2>) -> Array<bool, Ix2>
+    where S: Data<Elem=bool>, T: Data<Elem=bool>
+{
+    zip_map_mask(a, b, |x, y| x != y)
+}
+
+/// Compute the pixel-wise NOT of a mask.
+/// # Example:
+/// ```
+/// let a = ndarray::arr2(&[[true, false], [false, true]]);
+/// assert_eq!(simplecv::arithmetic::bitwise_not(&a), ndarray::arr2(&[[false, true], [true, false]]));
+/// ```
+pub fn bitwise_not<S>(a: &ArrayBase<S, Ix2>) -> Array<bool, Ix2>
+    where S: Data<Elem=bool>
+{
+    a.mapv(|x| !x)
+}
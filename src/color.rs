@@ -1,58 +1,111 @@
 //! Color transformation and enhancement.
 //!
 use ndarray::prelude::*;
-use ndarray::{Data, DataMut};
+use ndarray::{Data, DataMut, Zip};
+use num::traits::Float;
 use super::utils::f2u;
+use super::filter::{gaussian_smooth_sigma, BorderType};
 
-/// Transform an RGB image to grayscale image.
+/// The BT.601 weights of red, green and blue used by `rgb2gray`.
+const BT601_WEIGHTS: [f64; 3] = [0.299, 0.587, 0.114];
+
+/// Transform an RGB image to grayscale image with custom channel weights.
 ///
 /// The output buffer is allocated by users.
-/// The weights are 0.299, 0.587 and 0.114 for red, green and blue respectively.
-pub fn rgb2gray_<A, B>(img: &ArrayBase<A, Ix3>, out: &mut ArrayBase<B, Ix2>)
-    where A: Data<Elem=f64>, B: DataMut<Elem=f64>
+/// `weights` are the weights for red, green and blue respectively, which
+/// are normalized to sum 1 before applied. RGBA images are accepted and the
+/// alpha channel is ignored.
+pub fn rgb2gray_weighted_<F, A, B>(img: &ArrayBase<A, Ix3>, weights: [f64; 3], out: &mut ArrayBase<B, Ix2>)
+    where F: Float, A: Data<Elem=F>, B: DataMut<Elem=F>
 {
     let shape = img.shape();
     let h = shape[0];
     let w = shape[1];
     let c = shape[2];
-    let rgb_weights = [0.299, 0.587, 0.114];
-    assert_eq!(c, 3);
+    let weight_sum: f64 = weights.iter().sum();
+    assert!(weight_sum != 0.0, "Sum of weights must not be zero in rgb2gray_weighted_().");
+    let rgb_weights: Vec<F> = weights.iter().map(|w| F::from(w / weight_sum).unwrap()).collect();
+    assert!(c == 3 || c == 4, "Only RGB or RGBA images are supported in rgb2gray_weighted_().");
     let output_shape = out.shape();
     assert_eq!(h, output_shape[0]);
     assert_eq!(w, output_shape[1]);
-    for i in 0..h {
-        for j in 0..w {
-            let pixel = img.slice(s![i as usize, j as usize, ..]);
-            let gray = pixel.into_iter()
-                            .zip(&rgb_weights)
-                            .map(|(p, w)|(*p) * (*w))
-                            .fold(0.0, |acc, x| acc + x);
-            out[[i as usize, j as usize]] = gray;
-        }
-    }
+    Zip::from(out)
+        .and(img.index_axis(Axis(2), 0))
+        .and(img.index_axis(Axis(2), 1))
+        .and(img.index_axis(Axis(2), 2))
+        .apply(|o, &r, &g, &b| *o = r * rgb_weights[0] + g * rgb_weights[1] + b * rgb_weights[2]);
+}
+
+/// Transform an RGB image to grayscale image with custom channel weights.
+///
+/// `weights` are the weights for red, green and blue respectively, which
+/// are normalized to sum 1 before applied. For example, the BT.709 weights
+/// are `[0.2126, 0.7152, 0.0722]`. RGBA images are accepted and the alpha
+/// channel is ignored.
+/// # Example:
+/// ```
+/// let img_color = ndarray::arr3(&[[[0.3, 0.6, 0.9], [0.0, 0.0, 0.3]]]);
+/// let gray = simplecv::color::rgb2gray_weighted(&img_color, [1.0, 1.0, 1.0]);
+/// let max_diff_val = simplecv::utils::max_diff(&gray, &ndarray::arr2(&[[0.6, 0.1]]));
+/// assert!(max_diff_val < 1e-9);
+///
+/// // same as the weighted sum of each pixel
+/// let img_color = ndarray::Array::from_shape_fn((16, 16, 3), |(i, j, c)| ((i * 7 + j * 3 + c * 5) % 11) as f64 / 10.0);
+/// let gray = simplecv::color::rgb2gray_weighted(&img_color, [0.2126, 0.7152, 0.0722]);
+/// for ((i, j), v) in gray.indexed_iter() {
+///     let expected = 0.2126 * img_color[[i, j, 0]] + 0.7152 * img_color[[i, j, 1]] + 0.0722 * img_color[[i, j, 2]];
+///     assert!((v - expected).abs() < 1e-12);
+/// }
+/// ```
+pub fn rgb2gray_weighted<F, A>(img: &ArrayBase<A, Ix3>, weights: [f64; 3]) -> Array<F, Ix2>
+    where F: Float, A: Data<Elem=F>
+{
+    let shape = img.shape();
+    let h = shape[0];
+    let w = shape[1];
+    let c = shape[2];
+    assert!(c == 3 || c == 4, "Only RGB or RGBA images are supported in rgb2gray_weighted().");
+    let mut buffer = Array::zeros((h, w));
+    rgb2gray_weighted_(img, weights, &mut buffer);
+    buffer
+}
+
+/// Transform an RGB image to grayscale image.
+///
+/// The output buffer is allocated by users.
+/// The weights are 0.299, 0.587 and 0.114 for red, green and blue respectively.
+pub fn rgb2gray_<F, A, B>(img: &ArrayBase<A, Ix3>, out: &mut ArrayBase<B, Ix2>)
+    where F: Float, A: Data<Elem=F>, B: DataMut<Elem=F>
+{
+    rgb2gray_weighted_(img, BT601_WEIGHTS, out);
 }
 
 /// Transform an RGB image to grayscale image.
 /// 
 /// The weights are 0.299, 0.587 and 0.114 for red, green and blue respectively.
+/// RGBA images are accepted and the alpha channel is ignored. Besides `f64`,
+/// images of any `Float` type such as `f32` are accepted.
 /// # Example:
 /// ```
 /// let img_color = ndarray::arr3(&[[[0.0588, 1.0000, 0.4902], [0.0784, 0.9412, 0.4314]]]);
 /// let gray = simplecv::color::rgb2gray(&img_color);
 /// let max_diff_val = simplecv::utils::max_diff(&gray, &ndarray::arr2(&[[0.6605, 0.6251]]));
 /// assert!(max_diff_val < 1e-3);
+///
+/// // the alpha channel does not change the result
+/// let img_rgba = ndarray::arr3(&[[[0.0588, 1.0000, 0.4902, 0.2], [0.0784, 0.9412, 0.4314, 1.0]]]);
+/// assert_eq!(simplecv::color::rgb2gray(&img_rgba), gray);
+///
+/// // the same conversion on f32 arrays
+/// let gray_f32 = simplecv::color::rgb2gray(&img_color.mapv(|v| v as f32));
+/// for (a, b) in gray_f32.iter().zip(gray.iter()) {
+///     assert!((f64::from(*a) - b).abs() < 1e-6);
+/// }
 /// ```
-pub fn rgb2gray<A>(img: &ArrayBase<A, Ix3>) -> Array<f64, Ix2> 
-    where A:Data<Elem=f64>
+pub fn rgb2gray<F, A>(img: &ArrayBase<A, Ix3>) -> Array<F, Ix2>
+    where F: Float, A: Data<Elem=F>
 {
-    let shape = img.shape();
-    let h = shape[0];
-    let w = shape[1];
-    let c = shape[2];
-    assert_eq!(c, 3);
-    let mut buffer = Array::zeros((h as usize, w as usize));
-    rgb2gray_(img, &mut buffer);
-    buffer
+    rgb2gray_weighted(img, BT601_WEIGHTS)
 }
 
 /// Histogram equalization of a grayscale image.
@@ -63,8 +116,8 @@ pub fn histeq_<A, B>(img: &ArrayBase<A, Ix2>, out:&mut ArrayBase<B, Ix2>)
     where A: Data<Elem=f64>, B:DataMut<Elem=f64>
 {
     let shape = img.shape();
-    let h = shape[0] as usize;
-    let w = shape[1] as usize;
+    let h = shape[0];
+    let w = shape[1];
     fn hist256cdf<A>(img: &ArrayBase<A, Ix2>) -> [f64; 256]
         where A: Data<Elem=f64>
     {
@@ -73,18 +126,20 @@ pub fn histeq_<A, B>(img: &ArrayBase<A, Ix2>, out:&mut ArrayBase<B, Ix2>)
             hist[f2u(*v) as usize] += 1.0;
         }
         for i in 1usize..256usize{
-            hist[i] = hist[i-1] + hist[i];
+            hist[i] += hist[i-1];
         }
-        let maxval = hist[255] as f64;
-        for i in 0usize..256usize{
-            hist[i] = hist[i] / maxval
+        let maxval = hist[255];
+        for h in hist.iter_mut(){
+            *h /= maxval
         }
         hist
     }
     let img_hist = hist256cdf(img);
     for i in 0usize..h {
         for j in 0usize..w {
-            out[[i, j]] = img_hist[(img[[i, j]] * 255.0 + 0.5) as usize];
+            // clamp the index, as rounding may exceed 255 for values around 1.0
+            let idx = usize::min((img[[i, j]] * 255.0 + 0.5) as usize, 255);
+            out[[i, j]] = img_hist[idx];
         }
     }
 }
@@ -93,13 +148,511 @@ pub fn histeq_<A, B>(img: &ArrayBase<A, Ix2>, out:&mut ArrayBase<B, Ix2>)
 ///
 /// Implementated following the OpenCV toturial on 
 /// [histogram equalization](https://docs.opencv.org/3.1.0/d5/daf/tutorial_py_histogram_equalization.html)
+/// # Example:
+/// ```
+/// // pure white pixels, including one above 1.0 which rounds past the last bin
+/// let img = ndarray::arr2(&[[0.0, 0.5], [1.0, 1.01]]);
+/// let equalized = simplecv::color::histeq(&img);
+/// assert_eq!(equalized[[1, 0]], 1.0);
+/// assert_eq!(equalized[[1, 1]], 1.0);
+/// assert_eq!(equalized[[0, 0]], 0.25);
+/// ```
 pub fn histeq<A>(img: &ArrayBase<A, Ix2>) -> Array<f64, Ix2> 
     where A: Data<Elem=f64>
 {
     let shape = img.shape();
-    let h = shape[0] as usize;
-    let w = shape[1] as usize;
+    let h = shape[0];
+    let w = shape[1];
     let mut buffer = Array::zeros((h, w));
     histeq_(img, &mut buffer);
     buffer
 }
+
+/// Multiply each pixel of an RGB image by a 3x3 matrix and add a bias.
+///
+/// This is the shared implementation of linear color transforms.
+fn apply_color_matrix<A>(img: &ArrayBase<A, Ix3>, matrix: &[[f64; 3]; 3], bias: [f64; 3]) -> Array<f64, Ix3>
+    where A: Data<Elem=f64>
+{
+    let shape = img.shape();
+    let h = shape[0];
+    let w = shape[1];
+    assert_eq!(shape[2], 3);
+    let mut buffer = Array::zeros((h, w, 3));
+    for i in 0..h {
+        for j in 0..w {
+            for c in 0..3 {
+                buffer[[i, j, c]] = (0..3).fold(bias[c], |acc, k| acc + matrix[c][k] * img[[i, j, k]]);
+            }
+        }
+    }
+    buffer
+}
+
+/// Apply the sepia tone to an RGB image.
+///
+/// The output buffer is allocated by users. Each pixel is multiplied by the
+/// standard sepia matrix and the results are clipped to \[0.0, 1.0\].
+pub fn sepia_<A, B>(img: &ArrayBase<A, Ix3>, out: &mut ArrayBase<B, Ix3>)
+    where A: Data<Elem=f64>, B: DataMut<Elem=f64>
+{
+    let sepia_matrix = [[0.393, 0.769, 0.189],
+                        [0.349, 0.686, 0.168],
+                        [0.272, 0.534, 0.131]];
+    assert_eq!(img.shape(), out.shape());
+    let toned = apply_color_matrix(img, &sepia_matrix, [0.0, 0.0, 0.0]);
+    out.assign(&toned.mapv(|v| v.clamp(0.0, 1.0)));
+}
+
+/// Apply the sepia tone to an RGB image.
+///
+/// Each pixel is multiplied by the standard sepia matrix and the results are
+/// clipped to \[0.0, 1.0\].
+/// # Example:
+/// ```
+/// let gray = ndarray::Array::from_elem((2, 2, 3), 0.5);
+/// let toned = simplecv::color::sepia(&gray);
+/// // a gray pixel turns into warm brown: red > green > blue
+/// assert!(toned[[0, 0, 0]] > toned[[0, 0, 1]]);
+/// assert!(toned[[0, 0, 1]] > toned[[0, 0, 2]]);
+/// assert!((toned[[1, 1, 2]] - 0.4685).abs() < 1e-9);
+/// ```
+pub fn sepia<A>(img: &ArrayBase<A, Ix3>) -> Array<f64, Ix3>
+    where A: Data<Elem=f64>
+{
+    let shape = img.shape();
+    let mut buffer = Array::zeros((shape[0], shape[1], shape[2]));
+    sepia_(img, &mut buffer);
+    buffer
+}
+
+/// Quantize each channel of an image to `levels` evenly-spaced values.
+///
+/// The output buffer is allocated by users. All values of `img` should be in
+/// \[0.0, 1.0\] and `levels` must be at least 2.
+pub fn quantize_<A, B>(img: &ArrayBase<A, Ix3>, levels: usize, out: &mut ArrayBase<B, Ix3>)
+    where A: Data<Elem=f64>, B: DataMut<Elem=f64>
+{
+    assert!(levels >= 2, "levels must be at least 2 in quantize_().");
+    assert_eq!(img.shape(), out.shape());
+    let step = (levels - 1) as f64;
+    out.zip_mut_with(img, |o, v| *o = (v.clamp(0.0, 1.0) * step).round() / step);
+}
+
+/// Quantize each channel of an image to `levels` evenly-spaced values.
+///
+/// It is also known as posterization. All values of `img` should be in
+/// \[0.0, 1.0\] and `levels` must be at least 2.
+/// # Example:
+/// ```
+/// let img = ndarray::Array::from_shape_fn((4, 4, 3), |(i, j, c)| (i * 12 + j * 3 + c) as f64 / 47.0);
+/// let posterized = simplecv::color::quantize(&img, 2);
+/// assert!(posterized.iter().all(|v| *v == 0.0 || *v == 1.0));
+/// ```
+pub fn quantize<A>(img: &ArrayBase<A, Ix3>, levels: usize) -> Array<f64, Ix3>
+    where A: Data<Elem=f64>
+{
+    let shape = img.shape();
+    let mut buffer = Array::zeros((shape[0], shape[1], shape[2]));
+    quantize_(img, levels, &mut buffer);
+    buffer
+}
+
+/// Reduce the colors of an RGB image with k-means clustering.
+///
+/// Pixels are clustered by their RGB vectors for at most `max_iter` iterations.
+/// Return the image in which each pixel is replaced by its cluster center,
+/// together with the palette of `k` cluster centers.
+///
+/// The centers are initialized deterministically: the first center is the
+/// first pixel, and each following center is the pixel farthest from all
+/// chosen centers. Therefore the result is reproducible.
+/// # Example:
+/// ```
+/// let img = ndarray::Array::from_shape_fn((4, 4, 3), |(i, _, c)| {
+///     if i < 2 { [0.9, 0.1, 0.1][c] } else { [0.1, 0.2, 0.8][c] }
+/// });
+/// let (recolored, palette) = simplecv::color::kmeans_colors(&img, 2, 10);
+/// assert_eq!(palette.len(), 2);
+/// let close = |a: &[f64; 3], b: [f64; 3]| (0..3).all(|c| (a[c] - b[c]).abs() < 1e-9);
+/// assert!(palette.iter().any(|p| close(p, [0.9, 0.1, 0.1])));
+/// assert!(palette.iter().any(|p| close(p, [0.1, 0.2, 0.8])));
+/// assert!(simplecv::utils::max_diff(&recolored, &img) < 1e-9);
+/// ```
+pub fn kmeans_colors<A>(img: &ArrayBase<A, Ix3>, k: usize, max_iter: usize) -> (Array<f64, Ix3>, Vec<[f64; 3]>)
+    where A: Data<Elem=f64>
+{
+    fn sqr_dis(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+        (0..3).fold(0.0, |acc, c| acc + (a[c] - b[c]).powi(2))
+    }
+    fn nearest(p: &[f64; 3], centers: &[[f64; 3]]) -> usize {
+        let mut best = 0;
+        for (idx, center) in centers.iter().enumerate() {
+            if sqr_dis(p, center) < sqr_dis(p, &centers[best]) {
+                best = idx;
+            }
+        }
+        best
+    }
+    let shape = img.shape();
+    let h = shape[0];
+    let w = shape[1];
+    assert_eq!(shape[2], 3);
+    assert!(k >= 1, "k must be at least 1 in kmeans_colors().");
+    assert!(h * w >= k, "The image must have at least k pixels in kmeans_colors().");
+    let pixels: Vec<[f64; 3]> = (0..h * w)
+        .map(|idx| {
+            let (i, j) = (idx / w, idx % w);
+            [img[[i, j, 0]], img[[i, j, 1]], img[[i, j, 2]]]
+        })
+        .collect();
+    // farthest-point initialization
+    let mut centers = vec![pixels[0]];
+    while centers.len() < k {
+        let mut far_idx = 0;
+        let mut far_dis = -1.0;
+        for (idx, p) in pixels.iter().enumerate() {
+            let d = sqr_dis(p, &centers[nearest(p, &centers)]);
+            if d > far_dis {
+                far_dis = d;
+                far_idx = idx;
+            }
+        }
+        centers.push(pixels[far_idx]);
+    }
+    let mut assignment = vec![0usize; pixels.len()];
+    for iter in 0..max_iter {
+        let mut changed = false;
+        for (idx, p) in pixels.iter().enumerate() {
+            let c = nearest(p, &centers);
+            if c != assignment[idx] {
+                assignment[idx] = c;
+                changed = true;
+            }
+        }
+        if iter > 0 && !changed {
+            break;
+        }
+        let mut sums = vec![[0.0f64; 3]; k];
+        let mut counts = vec![0usize; k];
+        for (p, c) in pixels.iter().zip(assignment.iter()) {
+            for ch in 0..3 {
+                sums[*c][ch] += p[ch];
+            }
+            counts[*c] += 1;
+        }
+        for c in 0..k {
+            if counts[c] > 0 {
+                for ch in 0..3 {
+                    centers[c][ch] = sums[c][ch] / counts[c] as f64;
+                }
+            }
+        }
+    }
+    let mut buffer = Array::zeros((h, w, 3));
+    for (idx, p) in pixels.iter().enumerate() {
+        let center = centers[nearest(p, &centers)];
+        for ch in 0..3 {
+            buffer[[idx / w, idx % w, ch]] = center[ch];
+        }
+    }
+    (buffer, centers)
+}
+
+/// White balance of an RGB image under the gray-world assumption.
+///
+/// Each channel is scaled so that the means of all channels become the mean
+/// gray level of the image, which removes color casts. The results are clipped
+/// to \[0.0, 1.0\]. Channels with zero mean are left unchanged.
+/// # Example:
+/// ```
+/// // a gray scene with an artificial blue cast
+/// let img = ndarray::Array::from_shape_fn((4, 4, 3), |(i, j, c)| {
+///     let gray = 0.2 + 0.04 * (i + j) as f64;
+///     if c == 2 { gray * 1.5 } else { gray * 0.9 }
+/// });
+/// let balanced = simplecv::color::white_balance_grayworld(&img);
+/// let mean = |c: usize| balanced.index_axis(ndarray::Axis(2), c).sum() / 16.0;
+/// assert!((mean(0) - mean(2)).abs() < 1e-9);
+/// assert!((mean(1) - mean(2)).abs() < 1e-9);
+/// ```
+pub fn white_balance_grayworld<A>(img: &ArrayBase<A, Ix3>) -> Array<f64, Ix3>
+    where A: Data<Elem=f64>
+{
+    let shape = img.shape();
+    assert_eq!(shape[2], 3);
+    let n = (shape[0] * shape[1]) as f64;
+    let means: Vec<f64> = img.axis_iter(Axis(2)).map(|channel| channel.sum() / n).collect();
+    let gray = means.iter().sum::<f64>() / 3.0;
+    let mut buffer = img.to_owned();
+    for (c, mut channel) in buffer.axis_iter_mut(Axis(2)).enumerate() {
+        if means[c] > 0.0 {
+            let gain = gray / means[c];
+            channel.mapv_inplace(|v| (v * gain).clamp(0.0, 1.0));
+        }
+    }
+    buffer
+}
+
+/// Single-scale retinex for illumination normalization.
+///
+/// The illumination is estimated by a Gaussian blur with standard deviation
+/// `sigma` (the kernel size is `2 * ceil(3 * sigma) + 1`), and the output is
+/// `log(img) - log(blurred)` rescaled to \[0.0, 1.0\]. A small epsilon is added
+/// before taking logarithms to avoid `log(0)`. It enhances details under uneven
+/// lighting.
+/// # Example:
+/// ```
+/// use simplecv::filter::BorderType;
+/// // a fine texture under a strong illumination gradient
+/// let img = ndarray::Array::from_shape_fn((16, 32), |(i, j)| {
+///     let texture = if (i + j) % 2 == 0 { 0.6 } else { 1.0 };
+///     texture * (0.1 + 0.8 * j as f64 / 31.0)
+/// });
+/// let out = simplecv::color::single_scale_retinex(&img, 3.0, BorderType::Reflect);
+/// let column_mean = |a: &ndarray::Array2<f64>, j: usize| a.column(j).sum() / 16.0;
+/// let before = column_mean(&img, 26) - column_mean(&img, 5);
+/// let after = column_mean(&out, 26) - column_mean(&out, 5);
+/// assert!(after.abs() < 0.2 * before.abs());
+/// ```
+pub fn single_scale_retinex<S>(img: &ArrayBase<S, Ix2>, sigma: f64, border: BorderType) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    let eps = 1e-6;
+    let ksize = 2 * (3.0 * sigma).ceil() as usize + 1;
+    let illumination = gaussian_smooth_sigma(img, ksize, sigma, border);
+    let mut out = Array::zeros((img.shape()[0], img.shape()[1]));
+    out.zip_mut_with(img, |o, v| *o = (v + eps).ln());
+    out.zip_mut_with(&illumination, |o, l| *o -= (l + eps).ln());
+    let min = out.fold(f64::INFINITY, |m, v| m.min(*v));
+    let max = out.fold(f64::NEG_INFINITY, |m, v| m.max(*v));
+    if max > min {
+        out.mapv_inplace(|v| (v - min) / (max - min));
+    } else {
+        out.fill(0.0);
+    }
+    out
+}
+
+/// Log transform of a grayscale image.
+///
+/// Each pixel is mapped to `c * log(1 + pixel)`, then the result is divided by
+/// its maximum so that it lies in \[0.0, 1.0\]. It expands dark regions and
+/// compresses bright regions, complementing gamma correction.
+/// # Example:
+/// ```
+/// let img = ndarray::arr2(&[[0.0, 0.1, 0.2, 0.5, 0.9, 1.0]]);
+/// let out = simplecv::color::log_transform(&img, 1.0);
+/// for j in 1..6 {
+///     assert!(out[[0, j]] > out[[0, j - 1]]);
+/// }
+/// assert_eq!(out[[0, 5]], 1.0);
+/// // dark pixels are brightened more than bright pixels
+/// assert!(out[[0, 1]] - img[[0, 1]] > out[[0, 4]] - img[[0, 4]]);
+/// ```
+pub fn log_transform<S>(img: &ArrayBase<S, Ix2>, c: f64) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    assert!(c > 0.0, "c must be positive in log_transform().");
+    let mut out = img.mapv(|v| c * v.ln_1p());
+    let max = out.fold(0.0f64, |m, v| m.max(*v));
+    if max > 0.0 {
+        out /= max;
+    }
+    out
+}
+
+/// Extract a bit plane of a grayscale image.
+///
+/// The image is quantized to 8 bits by `f2u`, and the bit `plane` (0 for the
+/// least significant bit, 7 for the most significant bit) of each pixel is
+/// returned as 0.0 or 1.0.
+/// # Example:
+/// ```
+/// let img = ndarray::Array::from_shape_fn((4, 8), |(i, j)| (i * 8 + j) as f64 / 31.0);
+/// let msb = simplecv::color::bit_plane(&img, 7);
+/// let thresholded = img.mapv(|v| if v >= 0.5 { 1.0 } else { 0.0 });
+/// assert_eq!(msb, thresholded);
+/// ```
+pub fn bit_plane<S>(img: &ArrayBase<S, Ix2>, plane: u8) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    assert!(plane < 8, "plane must be in 0..8 in bit_plane().");
+    img.mapv(|v| ((f2u(v) >> plane) & 1) as f64)
+}
+
+/// Map each pixel of a grayscale image through a lookup table.
+///
+/// Each pixel is quantized to 8 bits by `f2u` and replaced by the entry of
+/// `lut` at that index. Tone curves such as gamma correction can be computed
+/// once into a table and applied to many images.
+/// # Example:
+/// ```
+/// let img = ndarray::Array::from_shape_fn((4, 8), |(i, j)| ((i * 8 + j) * 8) as f64 / 255.0);
+/// let mut identity = [0.0; 256];
+/// let mut inverse = [0.0; 256];
+/// for i in 0..256 {
+///     identity[i] = i as f64 / 255.0;
+///     inverse[i] = 1.0 - i as f64 / 255.0;
+/// }
+/// assert_eq!(simplecv::color::apply_lut(&img, &identity), img);
+/// let inverted = simplecv::color::apply_lut(&img, &inverse);
+/// assert!(simplecv::utils::max_diff(&inverted, &img.mapv(|v| 1.0 - v)) < 1e-12);
+/// ```
+pub fn apply_lut<S>(img: &ArrayBase<S, Ix2>, lut: &[f64; 256]) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    img.mapv(|v| lut[f2u(v) as usize])
+}
+
+/// Gamma correction of an image.
+///
+/// Each pixel is mapped to `pixel^(1 / gamma)`, so `gamma > 1` brightens the
+/// image and `gamma < 1` darkens it.
+/// # Example:
+/// ```
+/// let img = ndarray::arr2(&[[0.0, 0.25, 1.0]]);
+/// let corrected = simplecv::color::gamma_correct(&img, 2.0);
+/// assert_eq!(corrected, ndarray::arr2(&[[0.0, 0.5, 1.0]]));
+/// ```
+pub fn gamma_correct<S, D>(img: &ArrayBase<S, D>, gamma: f64) -> Array<f64, D>
+    where S: Data<Elem=f64>, D: Dimension
+{
+    assert!(gamma > 0.0, "gamma must be positive in gamma_correct().");
+    img.mapv(|v| v.powf(1.0 / gamma))
+}
+
+/// Build a lookup table of gamma correction for `apply_lut`.
+///
+/// The entry `i` is `(i / 255)^(1 / gamma)`, the same as `gamma_correct`.
+/// # Example:
+/// ```
+/// use simplecv::color::{apply_lut, build_gamma_lut, gamma_correct};
+/// let img = ndarray::Array::from_shape_fn((8, 8), |(i, j)| (i * 8 + j) as f64 / 63.0);
+/// let lut = build_gamma_lut(2.2);
+/// let diff = simplecv::utils::max_diff(&apply_lut(&img, &lut), &gamma_correct(&img, 2.2));
+/// // one quantization step of the input may change the output by up to about 0.02
+/// assert!(diff < 0.03);
+/// ```
+pub fn build_gamma_lut(gamma: f64) -> [f64; 256] {
+    assert!(gamma > 0.0, "gamma must be positive in build_gamma_lut().");
+    let mut lut = [0.0; 256];
+    for (i, v) in lut.iter_mut().enumerate() {
+        *v = (i as f64 / 255.0).powf(1.0 / gamma);
+    }
+    lut
+}
+
+/// Build a lookup table of linear contrast adjustment for `apply_lut`.
+///
+/// The entry `i` is `alpha * (i / 255) + beta`, clipped to \[0.0, 1.0\].
+/// # Example:
+/// ```
+/// use simplecv::color::{apply_lut, build_contrast_lut};
+/// let img = ndarray::arr2(&[[0.0, 0.2, 0.8]]);
+/// let adjusted = apply_lut(&img, &build_contrast_lut(1.5, 0.1));
+/// assert!(simplecv::utils::max_diff(&adjusted, &ndarray::arr2(&[[0.1, 0.4, 1.0]])) < 1e-9);
+/// ```
+pub fn build_contrast_lut(alpha: f64, beta: f64) -> [f64; 256] {
+    let mut lut = [0.0; 256];
+    for (i, v) in lut.iter_mut().enumerate() {
+        *v = (alpha * i as f64 / 255.0 + beta).clamp(0.0, 1.0);
+    }
+    lut
+}
+
+/// Find thresholds splitting a grayscale image into `classes` groups with multi-level Otsu's method.
+///
+/// The 256-bin histogram of the image (quantized by `f2u`) is partitioned into
+/// `classes` consecutive groups maximizing the between-class variance, which
+/// is solved exactly by dynamic programming. The `classes - 1` thresholds are
+/// returned in increasing order, and pixels greater than the `k`-th threshold
+/// belong to a class above the `k`-th one.
+/// # Example:
+/// ```
+/// use simplecv::color::{multi_otsu, threshold_multi};
+/// // three modes around 0.1, 0.5 and 0.9
+/// let img = ndarray::Array::from_shape_fn((30, 30), |(i, j)| {
+///     [0.1, 0.5, 0.9][i / 10] + ((i * 7 + j * 3) % 5) as f64 / 100.0 - 0.02
+/// });
+/// let thresholds = multi_otsu(&img, 3);
+/// assert_eq!(thresholds.len(), 2);
+/// assert!(thresholds[0] > 0.12 && thresholds[0] < 0.48);
+/// assert!(thresholds[1] > 0.52 && thresholds[1] < 0.88);
+/// let levels = threshold_multi(&img, &thresholds);
+/// assert_eq!(levels[[5, 5]], 0.0);
+/// assert_eq!(levels[[15, 5]], 0.5);
+/// assert_eq!(levels[[25, 5]], 1.0);
+/// ```
+pub fn multi_otsu<S>(img: &ArrayBase<S, Ix2>, classes: usize) -> Vec<f64>
+    where S: Data<Elem=f64>
+{
+    assert!((2..=256).contains(&classes), "classes must be in [2, 256] in multi_otsu().");
+    let mut hist = [0.0f64; 256];
+    for &v in img.iter() {
+        hist[f2u(v) as usize] += 1.0;
+    }
+    // prefix sums of the counts and the first moments
+    let mut count = [0.0f64; 257];
+    let mut moment = [0.0f64; 257];
+    for i in 0..256 {
+        count[i + 1] = count[i] + hist[i];
+        moment[i + 1] = moment[i] + hist[i] * i as f64;
+    }
+    // the between-class variance is maximized by maximizing sum(m^2 / w) of classes
+    let score = |first: usize, last: usize| {
+        let w = count[last + 1] - count[first];
+        let m = moment[last + 1] - moment[first];
+        if w > 0.0 { m * m / w } else { 0.0 }
+    };
+    // best[k][i]: the best score of splitting bins i..256 into k + 1 classes
+    let mut best = vec![[f64::NEG_INFINITY; 256]; classes];
+    let mut split = vec![[0usize; 256]; classes];
+    for (i, b) in best[0].iter_mut().enumerate() {
+        *b = score(i, 255);
+    }
+    for k in 1..classes {
+        for i in 0..256 - k {
+            for end in i..256 - k {
+                let s = score(i, end) + best[k - 1][end + 1];
+                if s > best[k][i] {
+                    best[k][i] = s;
+                    split[k][i] = end;
+                }
+            }
+        }
+    }
+    let mut thresholds = Vec::with_capacity(classes - 1);
+    let mut start = 0;
+    for k in (1..classes).rev() {
+        let end = split[k][start];
+        thresholds.push((end as f64 + 0.5) / 255.0);
+        start = end + 1;
+    }
+    thresholds
+}
+
+/// Map each pixel to one of evenly spaced levels by thresholds.
+///
+/// With `n` thresholds in increasing order, a pixel greater than `k` of them
+/// is mapped to `k / n`, so the output levels are `0, 1 / n, ..., 1`.
+pub fn threshold_multi<S>(img: &ArrayBase<S, Ix2>, thresholds: &[f64]) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    assert!(!thresholds.is_empty(), "thresholds must not be empty in threshold_multi().");
+    let n = thresholds.len() as f64;
+    img.mapv(|v| thresholds.iter().filter(|&&t| v > t).count() as f64 / n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply_color_matrix_identity() {
+        let img = Array::from_shape_fn((3, 4, 3), |(i, j, c)| (i * 12 + j * 3 + c) as f64 / 36.0);
+        let identity = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+        let out = apply_color_matrix(&img, &identity, [0.0, 0.0, 0.0]);
+        assert_eq!(out, img);
+    }
+}
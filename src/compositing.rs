@@ -0,0 +1,157 @@
+//! Image compositing.
+//!
+use ndarray::prelude::*;
+use ndarray::Data;
+
+/// Max number of Jacobi iterations in `seamless_clone()`.
+const POISSON_MAX_ITER: usize = 10000;
+/// The iteration stops when no pixel changes more than this value.
+const POISSON_TOLERANCE: f64 = 1e-6;
+
+/// Blend a region of `src` into `dst` with Poisson image editing.
+///
+/// Pixels where `mask > 0.0` are cloned, and `mask` has the shape of the first
+/// two axes of `src`. The pixel `(i, j)` of `src` is placed at
+/// `(i + offset.0, j + offset.1)` of `dst`. Inside the region, the result
+/// keeps the gradients of `src`, while on its boundary it agrees with `dst`,
+/// so there is no visible seam.
+///
+/// The Poisson equation is solved for each channel by Jacobi iteration,
+/// which stops when no pixel changes more than 1e-6 in one iteration or after
+/// 10000 iterations. The iteration converges slowly for large regions.
+/// # Example:
+/// ```
+/// // a horizontal ramp as the destination and a flat bright patch as the source
+/// let dst = ndarray::Array::from_shape_fn((16, 16, 3), |(_, j, _)| j as f64 / 15.0);
+/// let src = ndarray::Array::from_elem((8, 8, 3), 0.9);
+/// let mask = ndarray::Array::from_shape_fn((8, 8), |(i, j)| {
+///     if i > 0 && i < 7 && j > 0 && j < 7 { 1.0 } else { 0.0 }
+/// });
+/// let cloned = simplecv::compositing::seamless_clone(&src, &dst, &mask, (4, 4));
+/// // the flat patch takes the ramp of the destination, leaving no seam
+/// assert!(simplecv::utils::max_diff(&cloned, &dst) < 1e-3);
+///
+/// // pasting the whole rectangle takes its boundary from the destination as well
+/// let full_mask = ndarray::Array::from_elem((8, 8), 1.0);
+/// let cloned = simplecv::compositing::seamless_clone(&src, &dst, &full_mask, (4, 4));
+/// assert!(simplecv::utils::max_diff(&cloned, &dst) < 1e-3);
+/// ```
+pub fn seamless_clone<S, T, M>(src: &ArrayBase<S, Ix3>, dst: &ArrayBase<T, Ix3>, mask: &ArrayBase<M, Ix2>,
+                               offset: (usize, usize)) -> Array<f64, Ix3>
+    where S: Data<Elem=f64>, T: Data<Elem=f64>, M: Data<Elem=f64>
+{
+    let src_h = src.shape()[0];
+    let src_w = src.shape()[1];
+    assert_eq!(mask.shape(), &[src_h, src_w], "mask must have the shape of src in seamless_clone().");
+    assert_eq!(src.shape()[2], dst.shape()[2], "Channels of images must be the same in seamless_clone().");
+    assert!(offset.0 + src_h <= dst.shape()[0] && offset.1 + src_w <= dst.shape()[1],
+            "src must be inside dst in seamless_clone().");
+    let dst_h = dst.shape()[0];
+    let dst_w = dst.shape()[1];
+    let neighbors = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+    let region: Vec<(usize, usize)> = mask.indexed_iter()
+                                          .filter(|(_, &m)| m > 0.0)
+                                          .map(|(idx, _)| idx)
+                                          .collect();
+    let mut out = dst.to_owned();
+    for c in 0..src.shape()[2] {
+        let src_c = src.index_axis(Axis(2), c);
+        let mut current = out.index_axis(Axis(2), c).to_owned();
+        for &(i, j) in &region {
+            current[[i + offset.0, j + offset.1]] = src_c[[i, j]];
+        }
+        // pixels outside the region never change, so the buffers can be swapped
+        let mut next = current.clone();
+        for _ in 0..POISSON_MAX_ITER {
+            let mut max_change = 0.0f64;
+            for &(i, j) in &region {
+                let (x, y) = (i + offset.0, j + offset.1);
+                let mut sum = 0.0;
+                let mut count = 0.0;
+                for &(di, dj) in &neighbors {
+                    let nx = x as i32 + di;
+                    let ny = y as i32 + dj;
+                    if nx < 0 || ny < 0 || nx >= dst_h as i32 || ny >= dst_w as i32 {
+                        continue;
+                    }
+                    // the guidance gradient of a neighbor outside `src` is read from the nearest pixel of `src`
+                    let ni = (i as i32 + di).max(0).min(src_h as i32 - 1) as usize;
+                    let nj = (j as i32 + dj).max(0).min(src_w as i32 - 1) as usize;
+                    // the neighbor is either unknown or a boundary value from `dst`
+                    sum += current[[nx as usize, ny as usize]] + src_c[[i, j]] - src_c[[ni, nj]];
+                    count += 1.0;
+                }
+                if count == 0.0 {
+                    continue;
+                }
+                let v = sum / count;
+                max_change = max_change.max((v - current[[x, y]]).abs());
+                next[[x, y]] = v;
+            }
+            std::mem::swap(&mut current, &mut next);
+            if max_change < POISSON_TOLERANCE {
+                break;
+            }
+        }
+        out.index_axis_mut(Axis(2), c).assign(&current);
+    }
+    out
+}
+
+/// Fill the pixels where `mask > 0.0` from their neighbors.
+///
+/// The masked pixels are initialized with the mean of the known pixels, then
+/// each of them is replaced by the mean of its 4-connected neighbors for
+/// `iterations` times, which diffuses the surrounding values into the region.
+/// It works well for thin regions such as scratches and text.
+/// # Example:
+/// ```
+/// let img = ndarray::Array::from_shape_fn((12, 12), |(i, j)| (i + 2 * j) as f64 / 36.0);
+/// let mask = ndarray::Array::from_shape_fn((12, 12), |(i, j)| {
+///     if (4..8).contains(&i) && (5..8).contains(&j) { 1.0 } else { 0.0 }
+/// });
+/// let damaged = &img * &mask.mapv(|m: f64| 1.0 - m);
+/// let restored = simplecv::compositing::inpaint(&damaged, &mask, 200);
+/// assert!(simplecv::utils::max_diff(&restored, &img) < 1e-3);
+/// ```
+pub fn inpaint<S, M>(img: &ArrayBase<S, Ix2>, mask: &ArrayBase<M, Ix2>, iterations: usize) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>, M: Data<Elem=f64>
+{
+    assert_eq!(img.shape(), mask.shape(), "mask must have the shape of img in inpaint().");
+    let height = img.shape()[0];
+    let width = img.shape()[1];
+    let region: Vec<(usize, usize)> = mask.indexed_iter()
+                                          .filter(|(_, &m)| m > 0.0)
+                                          .map(|(idx, _)| idx)
+                                          .collect();
+    let known = img.len() - region.len();
+    let known_mean = if known > 0 {
+        img.indexed_iter().filter(|(idx, _)| mask[*idx] <= 0.0).map(|(_, &v)| v).sum::<f64>() / known as f64
+    } else {
+        0.0
+    };
+    let mut out = img.to_owned();
+    for &idx in &region {
+        out[idx] = known_mean;
+    }
+    let neighbors = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+    for _ in 0..iterations {
+        let current = out.clone();
+        for &(i, j) in &region {
+            let mut sum = 0.0;
+            let mut count = 0.0;
+            for &(di, dj) in &neighbors {
+                let ni = i as i32 + di;
+                let nj = j as i32 + dj;
+                if ni >= 0 && nj >= 0 && ni < height as i32 && nj < width as i32 {
+                    sum += current[[ni as usize, nj as usize]];
+                    count += 1.0;
+                }
+            }
+            if count > 0.0 {
+                out[[i, j]] = sum / count;
+            }
+        }
+    }
+    out
+}
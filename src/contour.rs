@@ -0,0 +1,355 @@
+//! Contour extraction and analysis of binary images.
+//!
+use ndarray::prelude::*;
+use ndarray::Data;
+use std::collections::VecDeque;
+use super::filter::Connectivity;
+
+/// Offsets of the eight neighbors in clockwise order, starting from the west.
+const MOORE_NEIGHBORS: [(i32, i32); 8] = [(0, -1), (-1, -1), (-1, 0), (-1, 1),
+                                          (0, 1), (1, 1), (1, 0), (1, -1)];
+
+/// Trace the outer boundary of an object with Moore-neighbor following.
+///
+/// `start` must be the first pixel of the object in raster order.
+fn trace_boundary<S>(binary: &ArrayBase<S, Ix2>, start: (usize, usize)) -> Vec<(usize, usize)>
+    where S: Data<Elem=f64>
+{
+    let height = binary.shape()[0] as i32;
+    let width = binary.shape()[1] as i32;
+    let is_foreground = |p: (i32, i32)| {
+        p.0 >= 0 && p.1 >= 0 && p.0 < height && p.1 < width && binary[[p.0 as usize, p.1 as usize]] > 0.0
+    };
+    let start_pos = (start.0 as i32, start.1 as i32);
+    let mut contour = vec![start];
+    let mut current = start_pos;
+    // the west neighbor of the first pixel in raster order is always background
+    let mut backtrack = (start_pos.0, start_pos.1 - 1);
+    loop {
+        let offset = (backtrack.0 - current.0, backtrack.1 - current.1);
+        let k = MOORE_NEIGHBORS.iter().position(|d| *d == offset).unwrap();
+        let mut next = None;
+        for t in 1..=8 {
+            let (dx, dy) = MOORE_NEIGHBORS[(k + t) % 8];
+            let candidate = (current.0 + dx, current.1 + dy);
+            if is_foreground(candidate) {
+                let (bx, by) = MOORE_NEIGHBORS[(k + t + 7) % 8];
+                next = Some((candidate, (current.0 + bx, current.1 + by)));
+                break;
+            }
+        }
+        match next {
+            None => break, // a single-pixel object
+            Some((n, b)) => {
+                let n_usize = (n.0 as usize, n.1 as usize);
+                // stop when the first move is about to be repeated
+                if current == start_pos && contour.len() > 1 && n_usize == contour[1] {
+                    contour.pop();
+                    break;
+                }
+                contour.push(n_usize);
+                current = n;
+                backtrack = b;
+            }
+        }
+    }
+    contour
+}
+
+/// Trace the outer contours of all objects in a binary image.
+///
+/// Pixels with values larger than 0 are foreground, and objects are the
+/// 8-connected components of foreground pixels. For each object, the boundary
+/// pixels are returned in clockwise order (with the first axis pointing down),
+/// starting from the first pixel of the object in raster order. Holes are not
+/// traced. A single-pixel object gives a contour of one point.
+///
+/// The boundary is followed with the Moore-neighbor tracing algorithm.
+///
+/// # Example
+/// ```
+/// use simplecv::contour::trace_contours;
+/// let mut img = ndarray::Array::zeros((8, 8));
+/// for i in 2..6 {
+///     for j in 2..6 {
+///         img[[i, j]] = 1.0;
+///     }
+/// }
+/// img[[7, 7]] = 1.0;
+/// let contours = trace_contours(&img);
+/// assert_eq!(contours.len(), 2);
+/// assert_eq!(contours[0], vec![(2, 2), (2, 3), (2, 4), (2, 5), (3, 5), (4, 5),
+///                              (5, 5), (5, 4), (5, 3), (5, 2), (4, 2), (3, 2)]);
+/// assert_eq!(contours[1], vec![(7, 7)]);
+/// ```
+pub fn trace_contours<S>(binary: &ArrayBase<S, Ix2>) -> Vec<Vec<(usize, usize)>>
+    where S: Data<Elem=f64>
+{
+    let height = binary.shape()[0];
+    let width = binary.shape()[1];
+    let mut visited = Array::from_elem((height, width), false);
+    let mut contours = Vec::new();
+    let mut queue: VecDeque<(usize, usize)> = VecDeque::new();
+    for i in 0..height {
+        for j in 0..width {
+            if binary[[i, j]] <= 0.0 || visited[[i, j]] {
+                continue;
+            }
+            contours.push(trace_boundary(binary, (i, j)));
+            // mark the whole object as visited
+            visited[[i, j]] = true;
+            queue.push_back((i, j));
+            while let Some((x, y)) = queue.pop_front() {
+                for (dx, dy) in Connectivity::Eight.offsets() {
+                    let nx = x as i32 + dx;
+                    let ny = y as i32 + dy;
+                    if nx < 0 || ny < 0 || nx >= height as i32 || ny >= width as i32 {
+                        continue;
+                    }
+                    let (nx, ny) = (nx as usize, ny as usize);
+                    if binary[[nx, ny]] > 0.0 && !visited[[nx, ny]] {
+                        visited[[nx, ny]] = true;
+                        queue.push_back((nx, ny));
+                    }
+                }
+            }
+        }
+    }
+    contours
+}
+
+/// Simplify an open polyline with the Douglas-Peucker algorithm.
+fn douglas_peucker(points: &[(usize, usize)], epsilon: f64, out: &mut Vec<(usize, usize)>) {
+    let first = points[0];
+    let last = points[points.len() - 1];
+    let (x0, y0) = (first.0 as f64, first.1 as f64);
+    let (x1, y1) = (last.0 as f64, last.1 as f64);
+    let length = ((x1 - x0).powi(2) + (y1 - y0).powi(2)).sqrt();
+    let distance = |p: &(usize, usize)| {
+        let (x, y) = (p.0 as f64, p.1 as f64);
+        if length == 0.0 {
+            ((x - x0).powi(2) + (y - y0).powi(2)).sqrt()
+        } else {
+            ((x1 - x0) * (y0 - y) - (x0 - x) * (y1 - y0)).abs() / length
+        }
+    };
+    let mut far_idx = 0;
+    let mut far_dis = 0.0;
+    for (idx, p) in points.iter().enumerate().take(points.len() - 1).skip(1) {
+        let d = distance(p);
+        if d > far_dis {
+            far_dis = d;
+            far_idx = idx;
+        }
+    }
+    if far_dis > epsilon {
+        douglas_peucker(&points[..=far_idx], epsilon, out);
+        out.pop();
+        douglas_peucker(&points[far_idx..], epsilon, out);
+    } else {
+        out.push(first);
+        if points.len() > 1 {
+            out.push(last);
+        }
+    }
+}
+
+/// Approximate a closed contour with a polygon of fewer vertices.
+///
+/// The Douglas-Peucker algorithm is applied: the contour is split at the first
+/// point and the point farthest from it, and each part is simplified so that
+/// all removed points are within `epsilon` to the polygon. Return the vertices
+/// of the polygon in the order of the contour.
+///
+/// # Example
+/// ```
+/// use simplecv::contour::{approx_poly_dp, trace_contours};
+/// let mut img = ndarray::Array::zeros((10, 10));
+/// for i in 2..8 {
+///     for j in 2..8 {
+///         img[[i, j]] = 1.0;
+///     }
+/// }
+/// let contour = &trace_contours(&img)[0];
+/// let polygon = approx_poly_dp(contour, 1.0);
+/// assert_eq!(polygon, vec![(2, 2), (2, 7), (7, 7), (7, 2)]);
+/// ```
+pub fn approx_poly_dp(contour: &[(usize, usize)], epsilon: f64) -> Vec<(usize, usize)> {
+    if contour.len() < 3 {
+        return contour.to_vec();
+    }
+    let first = contour[0];
+    let sqr_dis = |p: &(usize, usize)| {
+        (p.0 as f64 - first.0 as f64).powi(2) + (p.1 as f64 - first.1 as f64).powi(2)
+    };
+    let mut far_idx = 0;
+    for (idx, p) in contour.iter().enumerate() {
+        if sqr_dis(p) > sqr_dis(&contour[far_idx]) {
+            far_idx = idx;
+        }
+    }
+    if far_idx == 0 {
+        return vec![first];
+    }
+    let mut closed = contour.to_vec();
+    closed.push(first);
+    let mut polygon = Vec::new();
+    douglas_peucker(&closed[..=far_idx], epsilon, &mut polygon);
+    polygon.pop();
+    douglas_peucker(&closed[far_idx..], epsilon, &mut polygon);
+    // the last point is the first point again
+    polygon.pop();
+    polygon
+}
+
+/// Compute the area enclosed by a closed contour with the shoelace formula.
+///
+/// Contours with fewer than 3 points have zero area. Note that the area is
+/// measured on the polygon through the pixel centers, so a traced `n` x `n`
+/// square has the area `(n - 1)^2`.
+///
+/// # Example
+/// ```
+/// use simplecv::contour::{contour_area, contour_perimeter, trace_contours};
+/// let mut img = ndarray::Array::zeros((13, 13));
+/// for i in 1..12 {
+///     for j in 1..12 {
+///         img[[i, j]] = 1.0;
+///     }
+/// }
+/// let contour = &trace_contours(&img)[0];
+/// assert!((contour_area(contour) - 100.0).abs() < 1e-9);
+/// assert!((contour_perimeter(contour) - 40.0).abs() < 1e-9);
+/// assert_eq!(contour_area(&[(1, 1), (2, 2)]), 0.0);
+/// ```
+pub fn contour_area(contour: &[(usize, usize)]) -> f64 {
+    if contour.len() < 3 {
+        return 0.0;
+    }
+    let n = contour.len();
+    let twice_area = (0..n).fold(0.0, |acc, k| {
+        let (x0, y0) = (contour[k].0 as f64, contour[k].1 as f64);
+        let (x1, y1) = (contour[(k + 1) % n].0 as f64, contour[(k + 1) % n].1 as f64);
+        acc + x0 * y1 - x1 * y0
+    });
+    twice_area.abs() / 2.0
+}
+
+/// Compute the perimeter of a closed contour.
+///
+/// It is the total length of the segments between consecutive points, including
+/// the segment from the last point back to the first one.
+pub fn contour_perimeter(contour: &[(usize, usize)]) -> f64 {
+    let n = contour.len();
+    if n < 2 {
+        return 0.0;
+    }
+    (0..n).fold(0.0, |acc, k| {
+        let dx = contour[k].0 as f64 - contour[(k + 1) % n].0 as f64;
+        let dy = contour[k].1 as f64 - contour[(k + 1) % n].1 as f64;
+        acc + (dx * dx + dy * dy).sqrt()
+    })
+}
+
+/// Compute the convex hull of points with the monotone chain algorithm.
+///
+/// The hull vertices are returned in counter-clockwise order without repeating
+/// the first vertex. Collinear points on the hull edges are dropped.
+fn convex_hull(points: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    sorted.dedup();
+    if sorted.len() < 3 {
+        return sorted;
+    }
+    let cross = |o: (f64, f64), a: (f64, f64), b: (f64, f64)| {
+        (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+    };
+    let mut hull: Vec<(f64, f64)> = Vec::new();
+    for pass in 0..2 {
+        let start = hull.len();
+        let iter: Box<dyn Iterator<Item=&(f64, f64)>> = if pass == 0 {
+            Box::new(sorted.iter())
+        } else {
+            Box::new(sorted.iter().rev())
+        };
+        for p in iter {
+            while hull.len() >= start + 2 && cross(hull[hull.len() - 2], hull[hull.len() - 1], *p) <= 0.0 {
+                hull.pop();
+            }
+            hull.push(*p);
+        }
+        // the last point is the first point of the other chain
+        hull.pop();
+    }
+    hull
+}
+
+/// Find the minimum-area bounding rectangle of points.
+///
+/// The rotating calipers method is applied on the convex hull of the points: one
+/// side of the minimum-area rectangle is always collinear with an edge of the
+/// hull. Return `(center_x, center_y, width, height, angle)`, where `angle` is in
+/// degrees within \[0, 90) and is the angle from the first axis to the side of
+/// length `width`.
+///
+/// # Example
+/// ```
+/// use simplecv::contour::min_area_rect;
+/// let theta = 30f64.to_radians();
+/// let (c, s) = (theta.cos(), theta.sin());
+/// // a 4 x 2 rectangle centered at (5, 6) rotated by 30 degrees
+/// let corners: Vec<(f64, f64)> = [(-2.0, -1.0), (2.0, -1.0), (2.0, 1.0), (-2.0, 1.0)]
+///     .iter()
+///     .map(|&(x, y)| (5.0 + x * c - y * s, 6.0 + x * s + y * c))
+///     .collect();
+/// let (cx, cy, w, h, angle) = min_area_rect(&corners);
+/// assert!((cx - 5.0).abs() < 1e-9 && (cy - 6.0).abs() < 1e-9);
+/// assert!((w - 4.0).abs() < 1e-9 && (h - 2.0).abs() < 1e-9);
+/// assert!((angle - 30.0).abs() < 1e-9);
+/// ```
+pub fn min_area_rect(points: &[(f64, f64)]) -> (f64, f64, f64, f64, f64) {
+    assert!(!points.is_empty(), "At least one point is required in min_area_rect().");
+    let hull = convex_hull(points);
+    if hull.len() == 1 {
+        return (hull[0].0, hull[0].1, 0.0, 0.0, 0.0);
+    }
+    let mut best: Option<(f64, f64, f64, f64, f64)> = None;
+    let mut best_area = f64::INFINITY;
+    let n = hull.len();
+    for k in 0..n {
+        let (p, q) = (hull[k], hull[(k + 1) % n]);
+        let length = ((q.0 - p.0).powi(2) + (q.1 - p.1).powi(2)).sqrt();
+        if length == 0.0 {
+            continue;
+        }
+        // unit vectors along and perpendicular to the edge
+        let (ux, uy) = ((q.0 - p.0) / length, (q.1 - p.1) / length);
+        let (vx, vy) = (-uy, ux);
+        let (mut min_u, mut max_u, mut min_v, mut max_v) = (f64::INFINITY, -f64::INFINITY,
+                                                            f64::INFINITY, -f64::INFINITY);
+        for h in hull.iter() {
+            let pu = h.0 * ux + h.1 * uy;
+            let pv = h.0 * vx + h.1 * vy;
+            min_u = min_u.min(pu);
+            max_u = max_u.max(pu);
+            min_v = min_v.min(pv);
+            max_v = max_v.max(pv);
+        }
+        let (width, height) = (max_u - min_u, max_v - min_v);
+        if width * height < best_area {
+            best_area = width * height;
+            let mu = (min_u + max_u) / 2.0;
+            let mv = (min_v + max_v) / 2.0;
+            let center = (mu * ux + mv * vx, mu * uy + mv * vy);
+            let mut angle = uy.atan2(ux).to_degrees().rem_euclid(180.0);
+            let (mut width, mut height) = (width, height);
+            if angle >= 90.0 {
+                angle -= 90.0;
+                std::mem::swap(&mut width, &mut height);
+            }
+            best = Some((center.0, center.1, width, height, angle));
+        }
+    }
+    best.unwrap()
+}
@@ -0,0 +1,39 @@
+//! Drawing and visualization.
+//!
+use ndarray::prelude::*;
+use ndarray::Data;
+
+/// Draw edges over a copy of an RGB image.
+///
+/// Pixels where `edges > 0.0`, e.g. the output of `canny_edge()`, are set to
+/// `color`, and the other pixels are kept.
+/// # Example:
+/// ```
+/// let img = ndarray::Array::from_elem((4, 4, 3), 0.5);
+/// let mut edges = ndarray::Array::zeros((4, 4));
+/// edges[[1, 2]] = 1.0;
+/// let overlay = simplecv::draw::overlay_edges(&img, &edges, [1.0, 0.0, 0.0]);
+/// assert_eq!(overlay[[1, 2, 0]], 1.0);
+/// assert_eq!(overlay[[1, 2, 1]], 0.0);
+/// assert_eq!(overlay[[1, 2, 2]], 0.0);
+/// for ((i, j, c), &v) in overlay.indexed_iter() {
+///     if (i, j) != (1, 2) {
+///         assert_eq!(v, img[[i, j, c]]);
+///     }
+/// }
+/// ```
+pub fn overlay_edges<A, S>(img: &ArrayBase<A, Ix3>, edges: &ArrayBase<S, Ix2>, color: [f64; 3]) -> Array<f64, Ix3>
+    where A: Data<Elem=f64>, S: Data<Elem=f64>
+{
+    assert_eq!(&img.shape()[..2], edges.shape(), "edges must have the shape of img in overlay_edges().");
+    assert_eq!(img.shape()[2], 3, "Only RGB images are supported in overlay_edges().");
+    let mut out = img.to_owned();
+    for ((i, j), &e) in edges.indexed_iter() {
+        if e > 0.0 {
+            for (c, &v) in color.iter().enumerate() {
+                out[[i, j, c]] = v;
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,465 @@
+//! Feature extraction and shape descriptors.
+//!
+use ndarray::prelude::*;
+use ndarray::Data;
+use super::filter::{access_img_border, sobel, BorderType};
+use super::transform::sample_bilinear;
+
+/// Spatial and central moments of an image.
+///
+/// `x` is the index of the first axis and `y` is the index of the second axis.
+/// `m_pq` is the sum of `x^p * y^q * img[[x, y]]`, and `mu_pq` is the same sum
+/// around the centroid.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Moments {
+    pub m00: f64,
+    pub m10: f64,
+    pub m01: f64,
+    pub m11: f64,
+    pub m20: f64,
+    pub m02: f64,
+    pub mu11: f64,
+    pub mu20: f64,
+    pub mu02: f64,
+    pub mu30: f64,
+    pub mu21: f64,
+    pub mu12: f64,
+    pub mu03: f64,
+}
+
+impl Moments {
+    /// The centroid `(m10 / m00, m01 / m00)`.
+    pub fn centroid(&self) -> (f64, f64) {
+        (self.m10 / self.m00, self.m01 / self.m00)
+    }
+}
+
+/// Compute the moments of an image up to the third order.
+///
+/// Pixel values are used as weights, so both binary masks and grayscale images
+/// are accepted.
+/// # Example
+/// ```
+/// use simplecv::features::moments;
+/// // a plus-shaped blob centered at (3, 4)
+/// let img = ndarray::Array::from_shape_fn((7, 9), |(i, j)| {
+///     if (i == 3 && j >= 2 && j <= 6) || (j == 4 && i >= 1 && i <= 5) { 1.0 } else { 0.0 }
+/// });
+/// let m = moments(&img);
+/// assert_eq!(m.m00, 9.0);
+/// let (cx, cy) = m.centroid();
+/// assert!((cx - 3.0).abs() < 1e-9 && (cy - 4.0).abs() < 1e-9);
+/// assert!(m.mu11.abs() < 1e-9);
+/// assert!((m.mu20 - 10.0).abs() < 1e-9 && (m.mu02 - 10.0).abs() < 1e-9);
+/// ```
+pub fn moments<S>(img: &ArrayBase<S, Ix2>) -> Moments
+    where S: Data<Elem=f64>
+{
+    let mut m = Moments { m00: 0.0, m10: 0.0, m01: 0.0, m11: 0.0, m20: 0.0, m02: 0.0,
+                          mu11: 0.0, mu20: 0.0, mu02: 0.0, mu30: 0.0, mu21: 0.0, mu12: 0.0, mu03: 0.0 };
+    for ((i, j), v) in img.indexed_iter() {
+        let (x, y) = (i as f64, j as f64);
+        m.m00 += v;
+        m.m10 += x * v;
+        m.m01 += y * v;
+        m.m11 += x * y * v;
+        m.m20 += x * x * v;
+        m.m02 += y * y * v;
+    }
+    if m.m00 != 0.0 {
+        let (cx, cy) = m.centroid();
+        for ((i, j), v) in img.indexed_iter() {
+            let (x, y) = (i as f64 - cx, j as f64 - cy);
+            m.mu11 += x * y * v;
+            m.mu20 += x * x * v;
+            m.mu02 += y * y * v;
+            m.mu30 += x * x * x * v;
+            m.mu21 += x * x * y * v;
+            m.mu12 += x * y * y * v;
+            m.mu03 += y * y * y * v;
+        }
+    }
+    m
+}
+
+/// Compute the seven Hu invariant moments of an image.
+///
+/// Hu moments are invariant to translation, scale and rotation, so they are
+/// classic shape descriptors for matching. They are computed from the
+/// normalized central moments `eta_pq = mu_pq / m00^(1 + (p + q) / 2)`. The
+/// seventh moment changes its sign under reflection.
+/// # Example
+/// ```
+/// use simplecv::features::hu_moments;
+/// let shape = ndarray::Array::from_shape_fn((12, 12), |(i, j)| {
+///     if (i >= 2 && i < 10 && j >= 2 && j < 5) || (i >= 7 && i < 10 && j >= 2 && j < 9) { 1.0 } else { 0.0 }
+/// });
+/// // rotate by 90 degrees
+/// let rotated = ndarray::Array::from_shape_fn((12, 12), |(i, j)| shape[[j, 11 - i]]);
+/// // scale by 2
+/// let scaled = ndarray::Array::from_shape_fn((24, 24), |(i, j)| shape[[i / 2, j / 2]]);
+/// let hu = hu_moments(&shape);
+/// let hu_rotated = hu_moments(&rotated);
+/// let hu_scaled = hu_moments(&scaled);
+/// for k in 0..7 {
+///     assert!((hu[k] - hu_rotated[k]).abs() < 1e-9);
+/// }
+/// for k in 0..2 {
+///     assert!((hu[k] - hu_scaled[k]).abs() < 0.05 * hu[k].abs());
+/// }
+/// ```
+pub fn hu_moments<S>(img: &ArrayBase<S, Ix2>) -> [f64; 7]
+    where S: Data<Elem=f64>
+{
+    let m = moments(img);
+    if m.m00 == 0.0 {
+        return [0.0; 7];
+    }
+    let eta = |mu: f64, order: i32| mu / m.m00.powf(1.0 + order as f64 / 2.0);
+    let (n20, n02, n11) = (eta(m.mu20, 2), eta(m.mu02, 2), eta(m.mu11, 2));
+    let (n30, n21, n12, n03) = (eta(m.mu30, 3), eta(m.mu21, 3), eta(m.mu12, 3), eta(m.mu03, 3));
+    let a = n30 + n12;
+    let b = n21 + n03;
+    [
+        n20 + n02,
+        (n20 - n02).powi(2) + 4.0 * n11 * n11,
+        (n30 - 3.0 * n12).powi(2) + (3.0 * n21 - n03).powi(2),
+        a * a + b * b,
+        (n30 - 3.0 * n12) * a * (a * a - 3.0 * b * b) + (3.0 * n21 - n03) * b * (3.0 * a * a - b * b),
+        (n20 - n02) * (a * a - b * b) + 4.0 * n11 * a * b,
+        (3.0 * n21 - n03) * a * (a * a - 3.0 * b * b) - (n30 - 3.0 * n12) * b * (3.0 * a * a - b * b),
+    ]
+}
+
+/// Compute the census transform of an image.
+///
+/// For each pixel, the neighbors in the `window` x `window` window are visited
+/// in row-major order skipping the center, and the `k`-th bit (from the least
+/// significant one) of the result is set when the `k`-th neighbor is brighter
+/// than the center. `window` must be odd and at most 5 so that the bits fit
+/// in a `u32`.
+/// # Example
+/// ```
+/// use simplecv::filter::BorderType;
+/// let patch = ndarray::arr2(&[[1.0, 0.0, 1.0],
+///                             [0.0, 0.5, 0.0],
+///                             [0.0, 0.0, 1.0]]);
+/// let census = simplecv::features::census_transform(&patch, 3, BorderType::Replicate);
+/// // neighbors 0, 2 and 7 are brighter than the center
+/// assert_eq!(census[[1, 1]], 0b1000_0101);
+/// ```
+pub fn census_transform<S>(src: &ArrayBase<S, Ix2>, window: usize, border: BorderType) -> Array<u32, Ix2>
+    where S: Data<Elem=f64>
+{
+    assert!(window % 2 == 1 && window <= 5, "window must be odd and at most 5 in census_transform().");
+    let half = (window / 2) as i32;
+    Array::from_shape_fn((src.shape()[0], src.shape()[1]), |(x, y)| {
+        let center = src[[x, y]];
+        let mut code = 0u32;
+        let mut bit = 0;
+        for dx in -half..=half {
+            for dy in -half..=half {
+                if dx == 0 && dy == 0 {
+                    continue;
+                }
+                if access_img_border(src, x as i32 + dx, y as i32 + dy, border) > center {
+                    code |= 1 << bit;
+                }
+                bit += 1;
+            }
+        }
+        code
+    })
+}
+
+/// Compute the 8-neighbor local binary pattern (LBP) code of each pixel.
+///
+/// The neighbors are visited clockwise starting from the top-left one, and
+/// the `k`-th bit of the code is set when the `k`-th neighbor is not darker
+/// than the center. Pixels outside the image are replicated from the border.
+/// # Example
+/// ```
+/// let img = ndarray::Array::from_elem((4, 4), 0.3);
+/// let codes = simplecv::features::lbp(&img);
+/// // all neighbors of a uniform region are equal to the center
+/// assert!(codes.iter().all(|&c| c == 255));
+/// ```
+pub fn lbp<S>(src: &ArrayBase<S, Ix2>) -> Array<u8, Ix2>
+    where S: Data<Elem=f64>
+{
+    const NEIGHBORS: [(i32, i32); 8] = [(-1, -1), (-1, 0), (-1, 1), (0, 1), (1, 1), (1, 0), (1, -1), (0, -1)];
+    Array::from_shape_fn((src.shape()[0], src.shape()[1]), |(x, y)| {
+        let center = src[[x, y]];
+        NEIGHBORS.iter().enumerate().fold(0u8, |code, (k, &(dx, dy))| {
+            if access_img_border(src, x as i32 + dx, y as i32 + dy, BorderType::Replicate) >= center {
+                code | (1 << k)
+            } else {
+                code
+            }
+        })
+    })
+}
+
+/// Compute the normalized histogram of LBP codes.
+///
+/// The histogram sums to 1 and can be used as a texture descriptor.
+/// # Example
+/// ```
+/// use simplecv::features::{lbp, lbp_histogram};
+/// let img = ndarray::Array::from_shape_fn((8, 8), |(i, j)| ((i + j) % 2) as f64);
+/// let hist = lbp_histogram(&lbp(&img));
+/// assert!((hist.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+/// ```
+pub fn lbp_histogram<S>(lbp_image: &ArrayBase<S, Ix2>) -> [f64; 256]
+    where S: Data<Elem=u8>
+{
+    let mut hist = [0.0; 256];
+    for &code in lbp_image.iter() {
+        hist[code as usize] += 1.0;
+    }
+    let n = lbp_image.len() as f64;
+    if n > 0.0 {
+        for v in hist.iter_mut() {
+            *v /= n;
+        }
+    }
+    hist
+}
+
+/// Compute the histograms of oriented gradients of image cells.
+///
+/// The image is divided into `cell_size` x `cell_size` cells, and the pixels
+/// which do not fill a whole cell on the bottom and right are ignored. The
+/// gradients are computed by `sobel()` with `border`. Unsigned orientations in
+/// \[0, 180) degrees are split into `nbins` bins, where 0 degree is the `x`
+/// direction of `sobel()`, i.e. the second axis. Each pixel votes for the bin of
+/// its orientation with its gradient magnitude. The output shape is
+/// `(height / cell_size, width / cell_size, nbins)`.
+/// # Example
+/// ```
+/// use simplecv::filter::BorderType;
+/// // a vertical edge: the intensity changes along the second axis
+/// let img = ndarray::Array::from_shape_fn((16, 16), |(_, j)| if j < 8 { 0.0 } else { 1.0 });
+/// let hist = simplecv::features::hog_cell(&img, 8, 9, BorderType::Replicate);
+/// assert_eq!(hist.shape(), &[2, 2, 9]);
+/// let cell: Vec<f64> = (0..9).map(|b| hist[[0, 1, b]]).collect();
+/// assert!(cell[0] > 0.0);
+/// assert!(cell.iter().skip(1).all(|&v| v < cell[0]));
+/// ```
+pub fn hog_cell<S>(src: &ArrayBase<S, Ix2>, cell_size: usize, nbins: usize, border: BorderType) -> Array<f64, Ix3>
+    where S: Data<Elem=f64>
+{
+    assert!(cell_size > 0 && nbins > 0, "cell_size and nbins must be positive in hog_cell().");
+    let gx = sobel(src, 3, 1, 0, border);
+    let gy = sobel(src, 3, 0, 1, border);
+    let cells_h = src.shape()[0] / cell_size;
+    let cells_w = src.shape()[1] / cell_size;
+    let bin_width = std::f64::consts::PI / nbins as f64;
+    let mut hist = Array::zeros((cells_h, cells_w, nbins));
+    for i in 0..cells_h * cell_size {
+        for j in 0..cells_w * cell_size {
+            let (dx, dy) = (gx[[i, j]], gy[[i, j]]);
+            let magnitude = (dx * dx + dy * dy).sqrt();
+            if magnitude == 0.0 {
+                continue;
+            }
+            let angle = dy.atan2(dx).rem_euclid(std::f64::consts::PI);
+            let bin = usize::min((angle / bin_width) as usize, nbins - 1);
+            hist[[i / cell_size, j / cell_size, bin]] += magnitude;
+        }
+    }
+    hist
+}
+
+/// Compute the HOG descriptor of an image.
+///
+/// The cell histograms of [hog_cell()](./fn.hog_cell.html) are grouped into
+/// blocks of `block_size` x `block_size` cells, which slide by one cell. Each
+/// block is L2-normalized, and the blocks are concatenated in row-major order.
+/// With `cells_h = height / cell_size` and `cells_w = width / cell_size`, the
+/// length of the descriptor is
+/// `(cells_h - block_size + 1) * (cells_w - block_size + 1) * block_size^2 * nbins`.
+/// # Example
+/// ```
+/// use simplecv::filter::BorderType;
+/// let img = ndarray::Array::from_shape_fn((64, 40), |(i, j)| ((i * 3 + j * 7) % 11) as f64 / 11.0);
+/// let descriptor = simplecv::features::hog_descriptor(&img, 8, 2, 9, BorderType::Replicate);
+/// // 8 x 5 cells give 7 x 4 blocks of 2 x 2 cells
+/// assert_eq!(descriptor.len(), 7 * 4 * 2 * 2 * 9);
+/// let block_norm: f64 = descriptor[..36].iter().map(|v| v * v).sum();
+/// assert!((block_norm - 1.0).abs() < 1e-6);
+/// ```
+pub fn hog_descriptor<S>(img: &ArrayBase<S, Ix2>, cell_size: usize, block_size: usize, nbins: usize,
+                         border: BorderType) -> Vec<f64>
+    where S: Data<Elem=f64>
+{
+    assert!(block_size > 0, "block_size must be positive in hog_descriptor().");
+    let cells = hog_cell(img, cell_size, nbins, border);
+    let cells_h = cells.shape()[0];
+    let cells_w = cells.shape()[1];
+    let mut descriptor = Vec::new();
+    if cells_h < block_size || cells_w < block_size {
+        return descriptor;
+    }
+    for bi in 0..=cells_h - block_size {
+        for bj in 0..=cells_w - block_size {
+            let start = descriptor.len();
+            for ci in bi..bi + block_size {
+                for cj in bj..bj + block_size {
+                    descriptor.extend((0..nbins).map(|b| cells[[ci, cj, b]]));
+                }
+            }
+            // a small epsilon avoids dividing by zero on flat blocks
+            let norm = (descriptor[start..].iter().map(|v| v * v).sum::<f64>() + 1e-12).sqrt();
+            for v in descriptor[start..].iter_mut() {
+                *v /= norm;
+            }
+        }
+    }
+    descriptor
+}
+
+/// Haar-like features on a window of an image.
+///
+/// `(row, col)` is the top-left corner of the window and `height` x `width` is
+/// its size. The window is split into equal rectangles, so `width` (or
+/// `height`) must be divisible by the number of rectangles along that axis.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum HaarFeature {
+    /// Two rectangles side by side: the right one minus the left one.
+    /// It responds to vertical edges.
+    EdgeVertical { row: usize, col: usize, height: usize, width: usize },
+    /// Two rectangles stacked: the bottom one minus the top one.
+    /// It responds to horizontal edges.
+    EdgeHorizontal { row: usize, col: usize, height: usize, width: usize },
+    /// Three rectangles side by side: the middle one minus the outer ones.
+    /// It responds to vertical lines.
+    LineVertical { row: usize, col: usize, height: usize, width: usize },
+    /// Three rectangles stacked: the middle one minus the outer ones.
+    /// It responds to horizontal lines.
+    LineHorizontal { row: usize, col: usize, height: usize, width: usize },
+}
+
+/// Evaluate a Haar-like feature in O(1) with an integral image.
+///
+/// `integral` is the output of
+/// [integral_image()](../utils/fn.integral_image.html) of the image.
+/// # Example
+/// ```
+/// use simplecv::features::{haar_feature, HaarFeature};
+/// // dark on the left and bright on the right
+/// let img = ndarray::Array::from_shape_fn((8, 8), |(_, j)| if j < 4 { 0.0 } else { 1.0 });
+/// let integral = simplecv::utils::integral_image(&img);
+/// let edge = HaarFeature::EdgeVertical { row: 0, col: 0, height: 8, width: 8 };
+/// assert_eq!(haar_feature(&integral, edge), 32.0);
+/// let flipped = simplecv::utils::integral_image(&img.mapv(|v| 1.0 - v));
+/// assert_eq!(haar_feature(&flipped, edge), -32.0);
+/// let horizontal = HaarFeature::EdgeHorizontal { row: 0, col: 0, height: 8, width: 8 };
+/// assert_eq!(haar_feature(&integral, horizontal), 0.0);
+/// ```
+pub fn haar_feature<S>(integral: &ArrayBase<S, Ix2>, feature: HaarFeature) -> f64
+    where S: Data<Elem=f64>
+{
+    let rect = |r: usize, c: usize, h: usize, w: usize| {
+        integral[[r + h, c + w]] - integral[[r, c + w]] - integral[[r + h, c]] + integral[[r, c]]
+    };
+    match feature {
+        HaarFeature::EdgeVertical { row, col, height, width } => {
+            assert!(width % 2 == 0, "width must be divisible by 2 in HaarFeature::EdgeVertical.");
+            let w = width / 2;
+            rect(row, col + w, height, w) - rect(row, col, height, w)
+        }
+        HaarFeature::EdgeHorizontal { row, col, height, width } => {
+            assert!(height % 2 == 0, "height must be divisible by 2 in HaarFeature::EdgeHorizontal.");
+            let h = height / 2;
+            rect(row + h, col, h, width) - rect(row, col, h, width)
+        }
+        HaarFeature::LineVertical { row, col, height, width } => {
+            assert!(width % 3 == 0, "width must be divisible by 3 in HaarFeature::LineVertical.");
+            let w = width / 3;
+            rect(row, col + w, height, w) - rect(row, col, height, w) - rect(row, col + 2 * w, height, w)
+        }
+        HaarFeature::LineHorizontal { row, col, height, width } => {
+            assert!(height % 3 == 0, "height must be divisible by 3 in HaarFeature::LineHorizontal.");
+            let h = height / 3;
+            rect(row + h, col, h, width) - rect(row, col, h, width) - rect(row + 2 * h, col, h, width)
+        }
+    }
+}
+
+/// The maximum number of iterations of `corner_subpixel()`.
+const SUBPIXEL_MAX_ITER: usize = 40;
+
+/// Refine corner locations to sub-pixel accuracy.
+///
+/// For a corner `q`, the gradient at each point `p` around it is orthogonal to
+/// `q - p`, because `p` lies either in a flat area or on an edge through `q`.
+/// `q` is found by solving the weighted least squares problem of this
+/// condition over a `window` x `window` neighborhood, where `window` must be
+/// odd, and the problem is solved again around the new location until it moves
+/// less than 0.001 pixel. The same method is used by OpenCV `cornerSubPix()`.
+///
+/// `corners` are the `(x, y)` integer locations, e.g. from a corner detector.
+/// A corner is kept at its input location if the refinement leaves the window.
+/// # Example
+/// ```
+/// use simplecv::features::corner_subpixel;
+/// // an anti-aliased corner of a bright quadrant at (10.3, 12.6)
+/// let (cx, cy) = (10.3, 12.6);
+/// let coverage = |t: f64, c: f64| (t + 0.5 - c).max(0.0).min(1.0);
+/// let img = ndarray::Array::from_shape_fn((24, 24), |(i, j)| {
+///     coverage(i as f64, cx) * coverage(j as f64, cy)
+/// });
+/// let refined = corner_subpixel(&img, &[(10, 13)], 9);
+/// let (x, y) = refined[0];
+/// let err = ((x - cx).powi(2) + (y - cy).powi(2)).sqrt();
+/// let integer_err = ((10.0 - cx).powi(2) + (13.0 - cy).powi(2)).sqrt();
+/// assert!(err < 0.2);
+/// assert!(err < integer_err);
+/// ```
+pub fn corner_subpixel<S>(src: &ArrayBase<S, Ix2>, corners: &[(usize, usize)], window: usize) -> Vec<(f64, f64)>
+    where S: Data<Elem=f64>
+{
+    assert!(window % 2 == 1 && window >= 3, "window must be an odd number >= 3 in corner_subpixel().");
+    let half = (window / 2) as i32;
+    let sigma = half as f64;
+    // derivatives along the first and the second axis
+    let gx = sobel(src, 3, 0, 1, BorderType::Replicate);
+    let gy = sobel(src, 3, 1, 0, BorderType::Replicate);
+    corners.iter().map(|&(x0, y0)| {
+        let start = (x0 as f64, y0 as f64);
+        let mut q = start;
+        for _ in 0..SUBPIXEL_MAX_ITER {
+            let (mut a, mut b, mut c, mut bx, mut by) = (0.0, 0.0, 0.0, 0.0, 0.0);
+            for di in -half..=half {
+                for dj in -half..=half {
+                    let (px, py) = (q.0 + di as f64, q.1 + dj as f64);
+                    let w = (-((di * di + dj * dj) as f64) / (2.0 * sigma * sigma)).exp();
+                    let u = sample_bilinear(&gx, px, py, BorderType::Replicate);
+                    let v = sample_bilinear(&gy, px, py, BorderType::Replicate);
+                    let (uu, uv, vv) = (w * u * u, w * u * v, w * v * v);
+                    a += uu;
+                    b += uv;
+                    c += vv;
+                    bx += uu * px + uv * py;
+                    by += uv * px + vv * py;
+                }
+            }
+            let det = a * c - b * b;
+            if det.abs() < 1e-12 {
+                break;
+            }
+            let next = ((c * bx - b * by) / det, (a * by - b * bx) / det);
+            let shift = (next.0 - q.0).powi(2) + (next.1 - q.1).powi(2);
+            q = next;
+            if shift < 1e-6 {
+                break;
+            }
+        }
+        if (q.0 - start.0).abs() > half as f64 || (q.1 - start.1).abs() > half as f64 {
+            start
+        } else {
+            q
+        }
+    }).collect()
+}
@@ -0,0 +1,165 @@
+//! 2D fast Fourier transform for frequency-domain processing.
+//!
+//! This module requires the `rustfft` feature.
+use ndarray::prelude::*;
+use ndarray::Data;
+use rustfft::{FftDirection, FftPlanner};
+pub use rustfft::num_complex::Complex;
+use super::filter::{access_img_border, BorderType};
+
+/// Apply an unnormalized 2D FFT to a complex array in place.
+fn fft2_inplace(data: &mut Array<Complex<f64>, Ix2>, direction: FftDirection) {
+    let mut planner = FftPlanner::new();
+    for axis in 0..2 {
+        let fft = planner.plan_fft(data.shape()[axis], direction);
+        for mut lane in data.lanes_mut(Axis(axis)) {
+            let mut line: Vec<Complex<f64>> = lane.iter().cloned().collect();
+            fft.process(&mut line);
+            for (dst, src) in lane.iter_mut().zip(line) {
+                *dst = src;
+            }
+        }
+    }
+}
+
+/// Compute the 2D discrete Fourier transform of an image.
+///
+/// The transform is not normalized, i.e., the DC component is the sum of all
+/// pixels. The normalization factor `1 / (height * width)` is applied by
+/// [ifft2()](./fn.ifft2.html).
+///
+/// # Example
+/// ```
+/// use simplecv::fft::*;
+/// let img = ndarray::Array::from_shape_fn((6, 5), |(i, j)| ((i * 3 + j * 7) % 11) as f64 / 11.0);
+/// let spectrum = fft2(&img);
+/// assert!((spectrum[[0, 0]].re - img.sum()).abs() < 1e-9);
+/// let recovered = ifft2(&spectrum).mapv(|v| v.re);
+/// assert!(simplecv::utils::max_diff(&recovered, &img) < 1e-9);
+/// ```
+pub fn fft2<S>(img: &ArrayBase<S, Ix2>) -> Array<Complex<f64>, Ix2>
+    where S: Data<Elem=f64>
+{
+    let mut buffer = img.mapv(|v| Complex::new(v, 0.0));
+    fft2_inplace(&mut buffer, FftDirection::Forward);
+    buffer
+}
+
+/// Compute the 2D inverse discrete Fourier transform of a spectrum.
+///
+/// The result is normalized by `1 / (height * width)`, so `ifft2(&fft2(&img))`
+/// recovers `img` in the real part.
+pub fn ifft2<S>(spectrum: &ArrayBase<S, Ix2>) -> Array<Complex<f64>, Ix2>
+    where S: Data<Elem=Complex<f64>>
+{
+    let mut buffer = spectrum.to_owned();
+    fft2_inplace(&mut buffer, FftDirection::Inverse);
+    let n = buffer.len() as f64;
+    buffer.mapv_inplace(|v| v / n);
+    buffer
+}
+
+/// Apply a linear filter to the source image in the frequency domain.
+///
+/// The result is the same as [filter()](../filter/fn.filter.html) up to
+/// floating-point error. The source image is padded according to `border`, then
+/// both the padded image and the kernel are transformed by FFT and multiplied.
+/// It is faster than `filter()` for large kernels (larger than about 15x15).
+///
+/// # Example
+/// ```
+/// use simplecv::filter::*;
+/// use simplecv::fft::filter_fft;
+/// let img = ndarray::Array::from_shape_fn((20, 17), |(i, j)| ((i * 5 + j * 3) % 13) as f64 / 13.0);
+/// let kernel = ndarray::Array::from_shape_fn((9, 9), |(i, j)| (i * 9 + j) as f64 / 81.0);
+/// let spatial = filter(&img, &kernel, BorderType::Replicate);
+/// let frequency = filter_fft(&img, &kernel, BorderType::Replicate);
+/// assert!(simplecv::utils::max_diff(&spatial, &frequency) < 1e-9);
+/// ```
+pub fn filter_fft<S, K>(src: &ArrayBase<S, Ix2>, kernel: &ArrayBase<K, Ix2>, border: BorderType) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>, K: Data<Elem=f64>
+{
+    let height = src.shape()[0];
+    let width = src.shape()[1];
+    let kh = kernel.shape()[0];
+    let kw = kernel.shape()[1];
+    let kcx = (kh / 2) as i32;
+    let kcy = (kw / 2) as i32;
+    let ph = height + kh - 1;
+    let pw = width + kw - 1;
+    let padded = Array::from_shape_fn((ph, pw), |(i, j)| {
+        access_img_border(src, i as i32 - kcx, j as i32 - kcy, border)
+    });
+    let mut padded_kernel = Array::zeros((ph, pw));
+    padded_kernel.slice_mut(s![..kh, ..kw]).assign(kernel);
+    let fs = fft2(&padded);
+    let fk = fft2(&padded_kernel);
+    // the circular cross-correlation is the inverse transform of F(src) * conj(F(kernel))
+    let product = Array::from_shape_fn((ph, pw), |(i, j)| fs[[i, j]] * fk[[i, j]].conj());
+    let result = ifft2(&product);
+    result.slice(s![..height, ..width]).mapv(|v| v.re)
+}
+
+/// Build the Gaussian low-pass mask `exp(-D^2 / (2 * cutoff^2))` of a spectrum.
+///
+/// `D` is the distance of a frequency to the DC component, in which the
+/// frequencies are wrapped into `[-len / 2, len / 2)` of each axis.
+fn gaussian_mask(height: usize, width: usize, cutoff: f64) -> Array<f64, Ix2> {
+    let wrap = |p: usize, len: usize| if 2 * p >= len { p as f64 - len as f64 } else { p as f64 };
+    Array::from_shape_fn((height, width), |(i, j)| {
+        let d2 = wrap(i, height).powi(2) + wrap(j, width).powi(2);
+        (-d2 / (2.0 * cutoff * cutoff)).exp()
+    })
+}
+
+/// Multiply the spectrum of an image by a mask and transform it back.
+fn apply_frequency_mask<S>(img: &ArrayBase<S, Ix2>, mask: &Array<f64, Ix2>) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    let mut spectrum = fft2(img);
+    spectrum.zip_mut_with(mask, |v, m| *v *= m);
+    ifft2(&spectrum).mapv(|v| v.re)
+}
+
+/// Gaussian low-pass filter in the frequency domain.
+///
+/// The spectrum is multiplied by `exp(-D^2 / (2 * cutoff^2))`, where `D` is the
+/// distance of a frequency to the DC component in frequency bins. The image is
+/// treated as periodic.
+///
+/// # Example
+/// ```
+/// use simplecv::fft::lowpass_gaussian;
+/// let board = ndarray::Array::from_shape_fn((16, 16), |(i, j)| ((i + j) % 2) as f64);
+/// let smoothed = lowpass_gaussian(&board, 3.0);
+/// let energy = |img: &ndarray::Array2<f64>| img.mapv(|v| (v - 0.5).powi(2)).sum();
+/// assert!(energy(&smoothed) < 0.01 * energy(&board));
+/// ```
+pub fn lowpass_gaussian<S>(img: &ArrayBase<S, Ix2>, cutoff: f64) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    assert!(cutoff > 0.0, "cutoff must be positive in lowpass_gaussian().");
+    let mask = gaussian_mask(img.shape()[0], img.shape()[1], cutoff);
+    apply_frequency_mask(img, &mask)
+}
+
+/// Gaussian high-pass filter in the frequency domain.
+///
+/// The spectrum is multiplied by `1 - exp(-D^2 / (2 * cutoff^2))`, where `D` is
+/// the distance of a frequency to the DC component in frequency bins. The image
+/// is treated as periodic.
+///
+/// # Example
+/// ```
+/// use simplecv::fft::highpass_gaussian;
+/// let flat = ndarray::Array::from_elem((8, 8), 0.7);
+/// let filtered = highpass_gaussian(&flat, 2.0);
+/// assert!(filtered.iter().all(|v| v.abs() < 1e-9));
+/// ```
+pub fn highpass_gaussian<S>(img: &ArrayBase<S, Ix2>, cutoff: f64) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    assert!(cutoff > 0.0, "cutoff must be positive in highpass_gaussian().");
+    let mask = gaussian_mask(img.shape()[0], img.shape()[1], cutoff).mapv(|m| 1.0 - m);
+    apply_frequency_mask(img, &mask)
+}
@@ -3,7 +3,8 @@
 use ndarray::prelude::*;
 use ndarray::{Data, DataMut};
 
-use super::{filter_, BorderType, sobel};
+use super::{filter_, BorderType, Connectivity, sobel};
+use crate::transform::sample_bilinear;
 use std::collections::VecDeque;
 
 /// The default 5x5 Gaussian kernel for smoothing.
@@ -60,7 +61,7 @@ fn obtain_direction<S>(gx: &ArrayBase<S, Ix2>, gy: &ArrayBase<S, Ix2>) -> Array<
     dir
 }
 fn clip_pixel(v:f64) -> f64 {
-    f64::min(f64::max(v, 0.0), 1.0)
+    v.clamp(0.0, 1.0)
 }
 // Perform NMS for edges.
 fn edge_nms<S, T>(dir: &ArrayBase<S, Ix2>, out: &mut ArrayBase<T, Ix2>)
@@ -128,8 +129,8 @@ fn edge_nms<S, T>(dir: &ArrayBase<S, Ix2>, out: &mut ArrayBase<T, Ix2>)
     }
 }
 /// Suppress all values lower than `min_val` and keep left values which are larger 
-/// than `max_val` or connected to large values.
-fn max_min_suppression<S>(max_val: f64, min_val: f64, out: &mut ArrayBase<S, Ix2>)
+/// than `max_val` or connected to large values under `connectivity`.
+fn max_min_suppression<S>(max_val: f64, min_val: f64, connectivity: Connectivity, out: &mut ArrayBase<S, Ix2>)
     where S: DataMut<Elem=f64>
 {
     assert!(max_val >= min_val);
@@ -140,32 +141,23 @@ fn max_min_suppression<S>(max_val: f64, min_val: f64, out: &mut ArrayBase<S, Ix2
         // check connectivity for all values >= max_val
     for i in 0usize..height {
         for j in 0usize..width {
-            if connected_check_buffer[[i, j]] == 0 {
-                if out[[i, j]] >= max_val {
-                    queue.push_back( i * width + j);
-                    connected_check_buffer[[i, j]] = 1;
-                    loop {
-                        match queue.pop_front() {
-                            Some(f) => {
-                                let x = (f / width) as i32;
-                                let y = (f % width) as i32;
-                                for dx in -1..1 {
-                                    for dy in -1..1 {
-                                        let nx = x + dx;
-                                        let ny = y + dy;
-                                        if nx < 0 || nx >= (height as i32) || ny < 0 || ny >= (width as i32) {
-                                            continue;
-                                        }
-                                        let nx = nx as usize;
-                                        let ny = ny as usize;
-                                        if out[[nx, ny]] >= min_val && connected_check_buffer[[nx, ny]] == 0{
-                                            connected_check_buffer[[nx, ny]] = 1;
-                                            queue.push_back(nx * width + ny);
-                                        }
-                                    }
-                                }
-                            }
-                            None => {break;}
+            if connected_check_buffer[[i, j]] == 0 && out[[i, j]] >= max_val {
+                queue.push_back( i * width + j);
+                connected_check_buffer[[i, j]] = 1;
+                while let Some(f) = queue.pop_front() {
+                    let x = (f / width) as i32;
+                    let y = (f % width) as i32;
+                    for (dx, dy) in connectivity.offsets() {
+                        let nx = x + dx;
+                        let ny = y + dy;
+                        if nx < 0 || nx >= (height as i32) || ny < 0 || ny >= (width as i32) {
+                            continue;
+                        }
+                        let nx = nx as usize;
+                        let ny = ny as usize;
+                        if out[[nx, ny]] >= min_val && connected_check_buffer[[nx, ny]] == 0{
+                            connected_check_buffer[[nx, ny]] = 1;
+                            queue.push_back(nx * width + ny);
                         }
                     }
                 }
@@ -182,45 +174,84 @@ fn max_min_suppression<S>(max_val: f64, min_val: f64, out: &mut ArrayBase<S, Ix2
 }
 
 /// Get histogram of a given bin size. All values in `src` are required to be in [0, 1]
+///
+/// The value 1.0 is counted in the last bin.
 fn get_histogram<S>(src: &ArrayBase<S, Ix2>, bin_size: usize) -> Vec<f64> 
     where S: Data<Elem=f64>
 {
-    let mut bins = vec![0f64; bin_size+1];
-    let mut sum = 0.0;
-    let height = src.shape()[0];
-    let width = src.shape()[1];
-    for i in 0usize..height {
-        for j in 0usize..width {
-            assert!(src[[i, j]] >= 0.0 && src[[i, j]] <=1.0);
-            let v = src[[i, j]] * (bin_size as f64);
-            let idx = v as usize;
-            bins[idx] = bins[idx] + 1.0;
-            sum = sum + 1.0;
-        }
+    assert!(bin_size > 0);
+    let mut bins = vec![0f64; bin_size];
+    for &v in src.iter() {
+        assert!((0.0..=1.0).contains(&v));
+        let idx = usize::min((v * bin_size as f64) as usize, bin_size - 1);
+        bins[idx] += 1.0;
     }
-    bins[bin_size - 2] = bins[bin_size - 1] + bins[bin_size - 2];
-    bins.truncate(bin_size);
-    for i in 0usize..bin_size {
-        bins[i] = bins[i] / sum;
+    let sum = src.len() as f64;
+    for b in bins.iter_mut() {
+        *b /= sum;
     }
     bins
 }
+
+/// Estimate the high and low thresholds of Canny's edge detector.
+///
+/// The histogram of `src` with 100 bins is scanned from the top until
+/// `max_val_percent` of pixels are covered to find the high threshold, and
+/// from the bottom until `min_val_percent` of pixels are covered to find the low
+/// threshold. `canny_edge_()` passes its input intensity image as `src`, whose
+/// values are required to be in \[0, 1\]. The return value is
+/// `(max_val, min_val)`, and `max_val >= min_val` is guaranteed.
+///
+/// # Example
+/// ```
+/// use simplecv::filter::canny::estimate_thresholds;
+/// let img = ndarray::Array::from_shape_fn((10, 10), |(i, j)| (i * 10 + j) as f64 / 100.0);
+/// let (max_val, min_val) = estimate_thresholds(&img, 0.2, 0.1);
+/// assert!((max_val - 0.8).abs() < 0.011);
+/// assert!((min_val - 0.1).abs() < 0.011);
+/// ```
+pub fn estimate_thresholds<S>(src: &ArrayBase<S, Ix2>, max_val_percent: f64, min_val_percent: f64) -> (f64, f64)
+    where S: Data<Elem=f64>
+{
+    let edge_hist = get_histogram(src, 100);
+    let mut max_val_left = max_val_percent;
+    let mut min_val_left = min_val_percent;
+    let mut max_val = 1.0;
+    let mut min_val = 0.0;
+    for i in 0usize..99 {
+        max_val_left -= edge_hist[99usize - i];
+        max_val -= 0.01;
+        if max_val_left <=0.0 { break; }
+    }
+    for h in edge_hist.iter().take(99) {
+        min_val_left -= h;
+        min_val += 0.01;
+        if min_val_left <=0.0 { break; }
+    }
+    if min_val > max_val{
+        std::mem::swap(&mut min_val, &mut max_val);
+    }
+    (max_val, min_val)
+}
+
 /// Simply Canny's edge detector. Output buffer is allocated by users.
 ///
 /// * `src`: input image 
 /// * `max_val_percent`: the ratio of strong edge.
 /// * `min_val_percent`: the ratio of noise (smaller than weak edge).
+/// * `connectivity`: how weak edges are linked to strong edges.
 /// * `out`: output buffer.
 ///
 /// Refered to [canny_edge()](./fn.canny_edge.html) for more details.
-pub fn canny_edge_<S, T>(src: &ArrayBase<S, Ix2>, max_val_percent: f64, min_val_percent: f64, border:BorderType, out: &mut ArrayBase<T, Ix2>)
+pub fn canny_edge_<S, T>(src: &ArrayBase<S, Ix2>, max_val_percent: f64, min_val_percent: f64, border:BorderType,
+                         connectivity: Connectivity, out: &mut ArrayBase<T, Ix2>)
     where S: Data<Elem=f64>, T:DataMut<Elem=f64>
 {
     // smooth the image, use out as the buffer
     filter_(src, &get_gaussian_filter(), border, out);
     // obtain gradients
-    let gx = sobel(&out, 3, 1, 0, border);
-    let gy = sobel(&out, 3, 0, 1, border);
+    let gx = sobel(out, 3, 1, 0, border);
+    let gy = sobel(out, 3, 0, 1, border);
     let gnorm = gx.mapv(|x| x.powi(2)) + gy.mapv(|x| x.powi(2));
     // put norm of gradient to out, which is almost the final result
     out.assign(&gnorm.mapv(f64::sqrt));
@@ -228,28 +259,9 @@ pub fn canny_edge_<S, T>(src: &ArrayBase<S, Ix2>, max_val_percent: f64, min_val_
     //non-maximum suppression
     edge_nms(&dir, out);
     //estimate min/max val
-    let edge_hist = get_histogram(src, 100);
-    let mut max_val_left = max_val_percent;
-    let mut min_val_left = min_val_percent;
-    let mut max_val = 1.0;
-    let mut min_val = 0.0;
-    for i in 0usize..99 {
-        max_val_left -= edge_hist[99usize - i];
-        max_val -= 0.01;
-        if max_val_left <=0.0 { break; }
-    }
-    for i in 0usize..99 {
-        min_val_left -= edge_hist[i];
-        min_val += 0.01;
-        if min_val_left <=0.0 { break; }
-    }
-    if min_val > max_val{
-        let k = min_val;
-        min_val = max_val;
-        max_val = k;
-    }
+    let (max_val, min_val) = estimate_thresholds(src, max_val_percent, min_val_percent);
     //suppress weak edges
-    max_min_suppression(max_val, min_val, out);
+    max_min_suppression(max_val, min_val, connectivity, out);
     // binarization
     let height = src.shape()[0];
     let width = src.shape()[1];
@@ -268,17 +280,122 @@ pub fn canny_edge_<S, T>(src: &ArrayBase<S, Ix2>, max_val_percent: f64, min_val_
 /// * `src`: input image 
 /// * `max_val_percent`: the ratio of strong edge.
 /// * `min_val_percent`: the ratio of noise (smaller than weak edge).
+/// * `connectivity`: how weak edges are linked to strong edges. `Connectivity::Eight`
+///   links more weak edges than `Connectivity::Four`.
 ///
 /// This functions requires that `max_val_percent` + `min_val_percent` <= 1.0. 
 /// The implementation follows [Canny edge
 /// detector](https://en.wikipedia.org/wiki/Canny_edge_detector), while 
 /// [fast-edge](https://code.google.com/archive/p/fast-edge/) is also referred.
-pub fn canny_edge<S>(src: &ArrayBase<S, Ix2>, max_val_percent:f64, min_val_percent:f64, border:BorderType) -> Array<f64, Ix2>
+pub fn canny_edge<S>(src: &ArrayBase<S, Ix2>, max_val_percent:f64, min_val_percent:f64, border:BorderType,
+                     connectivity: Connectivity) -> Array<f64, Ix2>
     where S: Data<Elem=f64>
 {
     let height: usize = src.shape()[0];
     let width: usize = src.shape()[1];
     let mut out = Array::zeros((height, width));
-    canny_edge_(src, max_val_percent, min_val_percent, border, &mut out);
+    canny_edge_(src, max_val_percent, min_val_percent, border, connectivity, &mut out);
     out
 }    
+
+/// Canny's edge detector with sub-pixel edge localization.
+///
+/// Edges are first detected by [canny_edge()](./fn.canny_edge.html) with the same
+/// arguments and 8-connectivity. Then, for each edge pixel, a parabola is fitted to the gradient
+/// magnitude at the pixel and at its two neighbors one pixel away along the
+/// gradient direction. Edge pixels which are not a maximum along the gradient
+/// direction are dropped. The peak of the parabola is returned as the refined edge
+/// location `(x, y)`, in the same coordinate order as the array indices.
+///
+/// On clean step edges the refined locations are usually within 0.1 ~ 0.2 pixel
+/// of the true edge. The offset along the gradient direction is limited to half
+/// a pixel, so that a refined location never leaves its pixel.
+///
+/// # Example
+/// ```
+/// use simplecv::filter::*;
+/// use simplecv::filter::canny::canny_edge_subpixel;
+/// // a vertical step edge located at y = 7.3
+/// let img = ndarray::Array::from_shape_fn((16, 16), |(_, j)| {
+///     (j as f64 + 0.5 - 7.3).clamp(0.0, 1.0)
+/// });
+/// let edges = canny_edge_subpixel(&img, 0.5, 0.05, BorderType::Reflect);
+/// assert!(!edges.is_empty());
+/// for (_, y) in edges {
+///     assert!((y - 7.3).abs() < 0.2);
+/// }
+/// ```
+pub fn canny_edge_subpixel<S>(src: &ArrayBase<S, Ix2>, max_val_percent: f64, min_val_percent: f64, border: BorderType) -> Vec<(f64, f64)>
+    where S: Data<Elem=f64>
+{
+    let height: usize = src.shape()[0];
+    let width: usize = src.shape()[1];
+    let edge = canny_edge(src, max_val_percent, min_val_percent, border, Connectivity::Eight);
+    let mut smoothed = Array::zeros((height, width));
+    filter_(src, &get_gaussian_filter(), border, &mut smoothed);
+    let gx = sobel(&smoothed, 3, 1, 0, border);
+    let gy = sobel(&smoothed, 3, 0, 1, border);
+    let gnorm = (gx.mapv(|x| x.powi(2)) + gy.mapv(|x| x.powi(2))).mapv(f64::sqrt);
+    let mut points = Vec::new();
+    for i in 0usize..height {
+        for j in 0usize..width {
+            if edge[[i, j]] == 0.0 {
+                continue;
+            }
+            let norm = gnorm[[i, j]];
+            if norm <= 0.0 {
+                points.push((i as f64, j as f64));
+                continue;
+            }
+            // `gx` is the derivative along the second axis and `gy` along the first one
+            let dx = gy[[i, j]] / norm;
+            let dy = gx[[i, j]] / norm;
+            let m_prev = sample_bilinear(&gnorm, i as f64 - dx, j as f64 - dy, BorderType::Replicate);
+            let m_next = sample_bilinear(&gnorm, i as f64 + dx, j as f64 + dy, BorderType::Replicate);
+            if norm < m_prev || norm < m_next {
+                // not a maximum along the gradient direction
+                continue;
+            }
+            let denominator = m_prev - 2.0 * norm + m_next;
+            let offset = if denominator < 0.0 {
+                (0.5 * (m_prev - m_next) / denominator).clamp(-0.5, 0.5)
+            } else {
+                0.0
+            };
+            points.push((i as f64 + offset * dx, j as f64 + offset * dy));
+        }
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn max_min_suppression_connectivity() {
+        // a strong edge at the top-left corner followed by a diagonal weak chain
+        let mut chain = Array::zeros((4, 4));
+        chain[[0, 0]] = 0.9;
+        chain[[1, 1]] = 0.5;
+        chain[[2, 2]] = 0.5;
+        chain[[3, 3]] = 0.5;
+        let mut eight = chain.clone();
+        max_min_suppression(0.8, 0.3, Connectivity::Eight, &mut eight);
+        assert_eq!(eight, chain);
+        let mut four = chain.clone();
+        max_min_suppression(0.8, 0.3, Connectivity::Four, &mut four);
+        assert_eq!(four[[0, 0]], 0.9);
+        assert_eq!(four.sum(), 0.9);
+    }
+
+    #[test]
+    fn get_histogram_counts_max_value() {
+        let hist = get_histogram(&Array::from_elem((4, 5), 1.0), 100);
+        assert_eq!(hist.len(), 100);
+        assert_eq!(hist[99], 1.0);
+        assert!((hist.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        let hist = get_histogram(&Array::from_shape_fn((10, 10), |(i, j)| (i * 10 + j) as f64 / 99.0), 10);
+        assert!((hist.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+    }
+}
@@ -2,6 +2,9 @@
 
 use ndarray::prelude::*;
 use ndarray::{Data, DataMut};
+use num::traits::Float;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
 
 /// Representing the border type for filters.
 ///
@@ -9,13 +12,34 @@ use ndarray::{Data, DataMut};
 /// * Constant(v): a constant, i.e., vvvv|abcdefgh|vvvv
 /// * Reflect: reflect the image, i.e., edcb|abcdefgh|gfed
 /// * Replicate: copy the value at the border, i.e., aaaa|abcdefgh|hhhh
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug)]
 pub enum BorderType {
     Constant(f64),
     Reflect,
     Replicate
 }
 
+/// Representing the connectivity of pixels.
+///
+/// * Four: a pixel is connected to its up, down, left and right neighbors.
+/// * Eight: a pixel is also connected to its four diagonal neighbors.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Connectivity {
+    Four,
+    Eight
+}
+
+impl Connectivity {
+    /// Offsets of the neighbors of a pixel under this connectivity.
+    pub fn offsets(self) -> &'static [(i32, i32)] {
+        match self {
+            Connectivity::Four => &[(-1, 0), (0, -1), (0, 1), (1, 0)],
+            Connectivity::Eight => &[(-1, -1), (-1, 0), (-1, 1), (0, -1),
+                                     (0, 1), (1, -1), (1, 0), (1, 1)]
+        }
+    }
+}
+
 /// Compute the source location of the outside point.
 ///
 /// This function is used by `filter`. 
@@ -24,20 +48,27 @@ pub enum BorderType {
 /// use simplecv::filter::*;
 /// let nx = border_interpolate(-2, 10, BorderType::Reflect).unwrap();
 /// assert_eq!(nx, 2);
+/// let nx = border_interpolate(11, 10, BorderType::Reflect).unwrap();
+/// assert_eq!(nx, 7);
 /// ```
 /// 
 /// The function return None when border type is Constant.
 pub fn border_interpolate(p:i32, len:usize, border: BorderType) -> Option<usize> {
-    fn abs(a: i32) -> i32{
-        if a < 0 {
-            -a
-        } else {
-            a
-        }
-    }
     match border{
         BorderType::Constant(_) => None,
-        BorderType::Reflect => Some(abs(p % (len as i32)) as usize),
+        BorderType::Reflect => {
+            if len == 1 {
+                return Some(0);
+            }
+            // the reflected sequence is periodic, e.g. abcdefgh gfedcb abcdefgh
+            let period = 2 * (len as i32 - 1);
+            let m = p.rem_euclid(period);
+            if m < len as i32 {
+                Some(m as usize)
+            } else {
+                Some((period - m) as usize)
+            }
+        }
         BorderType::Replicate => {
             if p < 0 {
                 Some(0usize)
@@ -48,21 +79,55 @@ pub fn border_interpolate(p:i32, len:usize, border: BorderType) -> Option<usize>
     }
 }
 
+/// Get the value of a 1D array at a location which may be outside the array.
+///
+/// It is the 1D version of the border handling used by `filter`.
+/// ```
+/// use simplecv::filter::*;
+/// let a = ndarray::arr1(&[1.0, 2.0, 3.0, 4.0, 5.0]);
+/// assert_eq!(access_1d_border(&a, 2, BorderType::Reflect), 3.0);
+/// assert_eq!(access_1d_border(&a, -2, BorderType::Constant(0.5)), 0.5);
+/// assert_eq!(access_1d_border(&a, 7, BorderType::Constant(0.5)), 0.5);
+/// assert_eq!(access_1d_border(&a, -2, BorderType::Reflect), 3.0);
+/// assert_eq!(access_1d_border(&a, 6, BorderType::Reflect), 3.0);
+/// assert_eq!(access_1d_border(&a, -2, BorderType::Replicate), 1.0);
+/// assert_eq!(access_1d_border(&a, 6, BorderType::Replicate), 5.0);
+/// ```
+pub fn access_1d_border<S>(arr: &ArrayBase<S, Ix1>, p: i32, border: BorderType) -> f64
+    where S: Data<Elem=f64>
+{
+    if p >= 0 && p < arr.len() as i32 {
+        arr[p as usize]
+    } else {
+        match border {
+            BorderType::Constant(v) => v,
+            BorderType::Reflect|BorderType::Replicate => arr[border_interpolate(p, arr.len(), border).unwrap()]
+        }
+    }
+}
+
 /// Get the value of an image at a location which may be outside the image.
 ///
 /// This function is used by `filter`.
-fn access_img_border<S>(src: &ArrayBase<S, Ix2>, x:i32, y:i32, border: BorderType) -> f64 
-    where S:Data<Elem=f64> 
+pub(crate) fn access_img_border<A, S>(src: &ArrayBase<S, Ix2>, x:i32, y:i32, border: BorderType) -> A
+    where A: Float, S:Data<Elem=A>
 {
     if x >= 0 && y >= 0 && x < src.shape()[0] as i32 && y < src.shape()[1] as i32 {
         src[[x as usize, y as usize]]
     }
     else {
         match border {
-            BorderType::Constant(v) => v,
+            BorderType::Constant(v) => A::from(v).unwrap(),
             BorderType::Reflect|BorderType::Replicate => {
-                let nx = border_interpolate(x, src.shape()[0], border).unwrap();
-                let ny = border_interpolate(y, src.shape()[1], border).unwrap();
+                let interpolate = |p: i32, len: usize| {
+                    if p >= 0 && p < len as i32 {
+                        p as usize
+                    } else {
+                        border_interpolate(p, len, border).unwrap()
+                    }
+                };
+                let nx = interpolate(x, src.shape()[0]);
+                let ny = interpolate(y, src.shape()[1]);
                 src[[nx, ny]]
             }
         }
@@ -77,19 +142,32 @@ fn access_img_border<S>(src: &ArrayBase<S, Ix2>, x:i32, y:i32, border: BorderTyp
 /// The method of dealing with border situation is selected by `border`. By setting
 /// `border=Reflect`, you will get the default result of OpenCV.
 ///
-pub fn filter_<S, T, K>(src: &ArrayBase<S, Ix2>, kernel: &ArrayBase<K, Ix2>, border: BorderType, 
+pub fn filter_<A, S, T, K>(src: &ArrayBase<S, Ix2>, kernel: &ArrayBase<K, Ix2>, border: BorderType,
                out:&mut ArrayBase<T, Ix2>)
-    where S: Data<Elem=f64>, T: DataMut<Elem=f64>, K: Data<Elem=f64>
+    where A: Float, S: Data<Elem=A>, T: DataMut<Elem=A>, K: Data<Elem=A>
+{
+    let anchor = (kernel.shape()[0] / 2, kernel.shape()[1] / 2);
+    filter_anchored_(src, kernel, anchor, border, out);
+}
+
+/// Apply a linear filter whose origin is at `anchor` of the kernel.
+///
+/// The output buffer should be allocated by users. `out[[i, j]]` is the sum of
+/// `kernel[[ki, kj]] * src[[i + ki - anchor.0, j + kj - anchor.1]]`.
+pub fn filter_anchored_<A, S, T, K>(src: &ArrayBase<S, Ix2>, kernel: &ArrayBase<K, Ix2>, anchor: (usize, usize),
+                                    border: BorderType, out: &mut ArrayBase<T, Ix2>)
+    where A: Float, S: Data<Elem=A>, T: DataMut<Elem=A>, K: Data<Elem=A>
 {
     let kh = kernel.shape()[0];
     let kw = kernel.shape()[1];
-    let kcx = (kh / 2) as i32; // kernel center x
-    let kcy = (kw / 2) as i32; // kernel center x
+    assert!(anchor.0 < kh && anchor.1 < kw, "anchor must be inside the kernel.");
+    let kcx = anchor.0 as i32;
+    let kcy = anchor.1 as i32;
     let height = src.shape()[0];
     let width = src.shape()[1];
     for i in 0..height {
         for j in 0..width {
-            let mut val = 0.0f64;
+            let mut val = A::zero();
             for ki in 0..kh {
                 for kj in 0..kw {
                     let sx = i as i32 + ki as i32 - kcx;
@@ -103,10 +181,39 @@ pub fn filter_<S, T, K>(src: &ArrayBase<S, Ix2>, kernel: &ArrayBase<K, Ix2>, bor
     }
 }
 
+/// Apply a linear filter whose origin is at `anchor` of the kernel.
+///
+/// `filter()` is the same as anchoring at the kernel center `(kh / 2, kw / 2)`.
+/// # Example
+/// ```
+/// use simplecv::filter::*;
+/// let img = ndarray::Array::from_shape_fn((5, 5), |(i, j)| (i * 5 + j) as f64);
+/// let kernel = ndarray::arr2(&[[0.0, 1.0, 0.0], [2.0, 0.0, 0.0], [0.0, 0.0, 0.0]]);
+/// let centered = filter(&img, &kernel, BorderType::Replicate);
+/// let anchored = filter_anchored(&img, &kernel, (0, 0), BorderType::Replicate);
+/// // moving the anchor up-left by one pixel translates the output
+/// for i in 0..4 {
+///     for j in 0..4 {
+///         assert_eq!(anchored[[i, j]], centered[[i + 1, j + 1]]);
+///     }
+/// }
+/// ```
+pub fn filter_anchored<A, S, K>(src: &ArrayBase<S, Ix2>, kernel: &ArrayBase<K, Ix2>, anchor: (usize, usize),
+                                border: BorderType) -> Array<A, Ix2>
+    where A: Float, S: Data<Elem=A>, K: Data<Elem=A>
+{
+    let mut buffer = Array::zeros((src.shape()[0], src.shape()[1]));
+    filter_anchored_(src, kernel, anchor, border, &mut buffer);
+    buffer
+}
+
 /// Apply a linear filter to the source image.
 ///
 /// The method of dealing with border situation is selected by `border`. By setting
 /// `border=Reflect`, you will get the default result of OpenCV.
+///
+/// Besides `f64`, images of any `Float` type are accepted, e.g. `f32` which
+/// halves the memory of large images. The kernel has the same element type.
 /// 
 /// # Example
 /// ```
@@ -127,77 +234,409 @@ pub fn filter_<S, T, K>(src: &ArrayBase<S, Ix2>, kernel: &ArrayBase<K, Ix2>, bor
 ///                      [1.0, 3.0, 4.0, 3.0, 1.0]]);
 ///  let output = filter(&A, &kernel, BorderType::Constant(0.0));
 ///  assert_eq!(target, output);
+///
+///  // the same filter on f32 arrays
+///  let img = ndarray::Array::from_shape_fn((8, 8), |(i, j)| ((i * 3 + j * 5) % 7) as f64 / 7.0);
+///  let expected = filter(&img, &kernel, BorderType::Reflect);
+///  let output = filter(&img.mapv(|v| v as f32), &kernel.mapv(|v| v as f32), BorderType::Reflect);
+///  for (a, b) in output.iter().zip(expected.iter()) {
+///      assert!((f64::from(*a) - b).abs() < 1e-5);
+///  }
 /// ```
 ///
-pub fn filter<S, K>(src: &ArrayBase<S, Ix2>, kernel: &ArrayBase<K, Ix2>, border: BorderType) -> Array<f64, Ix2>
-    where S: Data<Elem=f64>, K: Data<Elem=f64>
+/// With `Replicate`, only the coordinate outside the image is moved to the border.
+/// ```
+///  use simplecv::filter::*;
+///  use ndarray::arr2;
+///  let img = arr2(&[[1.0, 2.0, 3.0],
+///                   [4.0, 5.0, 6.0],
+///                   [7.0, 8.0, 9.0]]);
+///  let kernel = ndarray::Array::from_elem((3, 3), 1.0);
+///  let output = filter(&img, &kernel, BorderType::Replicate);
+///  // (1 + 1 + 2) * 2 + (4 + 4 + 5)
+///  assert_eq!(output[[0, 0]], 21.0);
+///  // (5 + 6 + 6) + (8 + 9 + 9) * 2
+///  assert_eq!(output[[2, 2]], 69.0);
+///  // (2 + 3 + 3) * 2 + (5 + 6 + 6)
+///  assert_eq!(output[[0, 2]], 33.0);
+/// ```
+///
+/// With `Reflect`, pixels past the far border are reflected as well.
+/// ```
+///  use simplecv::filter::*;
+///  use ndarray::arr2;
+///  let img = arr2(&[[1.0, 2.0, 3.0, 4.0, 5.0]]);
+///  // take the pixel on the right, and the one two pixels below
+///  let right = arr2(&[[0.0, 0.0, 1.0]]);
+///  let output = filter(&img, &right, BorderType::Reflect);
+///  assert_eq!(output, arr2(&[[2.0, 3.0, 4.0, 5.0, 4.0]]));
+///  let below = arr2(&[[0.0], [0.0], [0.0], [0.0], [1.0]]);
+///  let output = filter(&img.t(), &below, BorderType::Reflect);
+///  assert_eq!(output, arr2(&[[3.0], [4.0], [5.0], [4.0], [3.0]]));
+/// ```
+///
+pub fn filter<A, S, K>(src: &ArrayBase<S, Ix2>, kernel: &ArrayBase<K, Ix2>, border: BorderType) -> Array<A, Ix2>
+    where A: Float, S: Data<Elem=A>, K: Data<Elem=A>
 {
     let shape = src.shape();
-    let height = shape[0] as usize;
-    let width = shape[1] as usize;
+    let height = shape[0];
+    let width = shape[1];
     let mut buffer = Array::zeros((height, width));
     filter_(src, kernel, border, &mut buffer);
     buffer
 }
 
-/// Generate a Gaussian kernel with the simplest method.
+/// Apply a separable linear filter to the source image.
+///
+/// The output buffer should be allocated by users, and `out` must have the
+/// exactly same shape of `src`. The result equals `filter_` with the kernel
+/// `kernel_x^T * kernel_y`, where `kernel_x` is applied along the first axis and
+/// `kernel_y` along the second axis. The intermediate result keeps full precision
+/// (it is not clipped to \[0, 1\]), and `border` is applied on both passes.
+pub fn filter_separable_<S, T, K, L>(src: &ArrayBase<S, Ix2>, kernel_x: &ArrayBase<K, Ix1>, kernel_y: &ArrayBase<L, Ix1>,
+                                     border: BorderType, out: &mut ArrayBase<T, Ix2>)
+    where S: Data<Elem=f64>, T: DataMut<Elem=f64>, K: Data<Elem=f64>, L: Data<Elem=f64>
+{
+    let kh = kernel_x.len();
+    let kw = kernel_y.len();
+    let kcx = (kh / 2) as i32;
+    let kcy = (kw / 2) as i32;
+    let height = src.shape()[0];
+    let width = src.shape()[1];
+    // pass along the second axis
+    let mut intermediate = Array::zeros((height, width));
+    for i in 0..height {
+        for j in 0..width {
+            let mut val = 0.0f64;
+            for kj in 0..kw {
+                let sy = j as i32 + kj as i32 - kcy;
+                val += access_img_border(src, i as i32, sy, border) * kernel_y[kj];
+            }
+            intermediate[[i, j]] = val;
+        }
+    }
+    // pass along the first axis. A constant border row outside the image is a
+    // row of `v`, so its value after the first pass is `v * sum(kernel_y)`.
+    let border = match border {
+        BorderType::Constant(v) => BorderType::Constant(v * kernel_y.sum()),
+        _ => border
+    };
+    for i in 0..height {
+        for j in 0..width {
+            let mut val = 0.0f64;
+            for ki in 0..kh {
+                let sx = i as i32 + ki as i32 - kcx;
+                val += access_img_border(&intermediate, sx, j as i32, border) * kernel_x[ki];
+            }
+            out[[i, j]] = val;
+        }
+    }
+}
+
+/// Apply a separable linear filter to the source image.
+///
+/// The result equals `filter` with the kernel `kernel_x^T * kernel_y`, where
+/// `kernel_x` is applied along the first axis and `kernel_y` along the second
+/// axis, while the cost for each pixel is `O(kh + kw)` instead of `O(kh * kw)`.
+/// The intermediate result keeps full precision and `border` is applied on both
+/// passes.
+///
+/// # Example
+/// ```
+/// use simplecv::filter::*;
+/// use ndarray::{arr1, Array};
+/// let img = Array::from_shape_fn((5, 6), |(i, j)| ((i * 7 + j * 3) % 5) as f64 / 4.0);
+/// // the sharpening kernel makes the intermediate result go outside [0, 1]
+/// let kx = arr1(&[-1.0, 3.0, -1.0]);
+/// let ky = arr1(&[-0.5, 2.0, -0.5]);
+/// let kernel = kx.view().insert_axis(ndarray::Axis(1)).dot(&ky.view().insert_axis(ndarray::Axis(0)));
+/// for &border in &[BorderType::Constant(0.3), BorderType::Reflect, BorderType::Replicate] {
+///     let separable = filter_separable(&img, &kx, &ky, border);
+///     let full = filter(&img, &kernel, border);
+///     assert!(simplecv::utils::max_diff(&separable, &full) < 1e-9);
+/// }
+/// // clipping the intermediate result to [0, 1] would give a different result
+/// let row = ky.view().insert_axis(ndarray::Axis(0));
+/// let col = kx.view().insert_axis(ndarray::Axis(1));
+/// let clipped = filter(&img, &row, BorderType::Reflect).mapv(|v| v.max(0.0).min(1.0));
+/// let naive = filter(&clipped, &col, BorderType::Reflect);
+/// let separable = filter_separable(&img, &kx, &ky, BorderType::Reflect);
+/// assert!(simplecv::utils::max_diff(&separable, &naive) > 0.1);
+/// ```
+pub fn filter_separable<S, K, L>(src: &ArrayBase<S, Ix2>, kernel_x: &ArrayBase<K, Ix1>, kernel_y: &ArrayBase<L, Ix1>,
+                                 border: BorderType) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>, K: Data<Elem=f64>, L: Data<Elem=f64>
+{
+    let mut buffer = Array::zeros((src.shape()[0], src.shape()[1]));
+    filter_separable_(src, kernel_x, kernel_y, border, &mut buffer);
+    buffer
+}
+
+/// Normalize a kernel so that its entries sum to 1.
+///
+/// Kernels whose sum is zero, such as derivative kernels, cannot be
+/// normalized and are returned unchanged.
+/// # Example
+/// ```
+/// let kernel = simplecv::filter::normalize_kernel(&ndarray::Array::ones((3, 5)));
+/// assert!(kernel.iter().all(|&v| (v - 1.0 / 15.0).abs() < 1e-12));
+/// let sobel = ndarray::arr2(&[[-1.0, 0.0, 1.0], [-2.0, 0.0, 2.0], [-1.0, 0.0, 1.0]]);
+/// assert_eq!(simplecv::filter::normalize_kernel(&sobel), sobel);
+/// ```
+pub fn normalize_kernel<S>(kernel: &ArrayBase<S, Ix2>) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    let sum = kernel.sum();
+    if sum.abs() < f64::EPSILON {
+        kernel.to_owned()
+    } else {
+        kernel / sum
+    }
+}
+
+/// The default standard deviation of a Gaussian kernel of `ksize`, which is
+/// `0.3 * ((ksize - 1) * 0.5 - 1) + 0.8`, the same as OpenCV.
+fn default_gaussian_sigma(ksize: usize) -> f64 {
+    0.3 * ((ksize as f64 - 1.0) * 0.5 - 1.0) + 0.8
+}
+
+/// Generate a Gaussian kernel with the default standard deviation.
+///
+/// The standard deviation is `0.3 * ((ksize - 1) * 0.5 - 1) + 0.8`, which is
+/// the default of OpenCV `getGaussianKernel()`, e.g. 1.1 for `ksize = 5`.
+/// # Example
+/// ```
+/// let kernel = simplecv::filter::gaussian_kernel_generator(5);
+/// // the product of the 1D Gaussian [0.070766, 0.24446, 0.369546, 0.24446, 0.070766]
+/// let reference = [0.026151, 0.090339, 0.136565, 0.090339, 0.026151];
+/// for j in 0..5 {
+///     assert!((kernel[[2, j]] - reference[j]).abs() < 1e-6);
+/// }
+/// assert!((kernel[[0, 0]] - 0.005008).abs() < 1e-6);
+/// assert!((kernel.sum() - 1.0).abs() < 1e-12);
+/// ```
 pub fn gaussian_kernel_generator(ksize: usize) -> Array<f64, Ix2>{
+    gaussian_kernel_generator_sigma(ksize, default_gaussian_sigma(ksize))
+}
+
+/// Generate a Gaussian kernel with standard deviation `sigma`.
+///
+/// The kernel is sampled from `exp(-d^2 / (2 * sigma^2))`, where `d` is the
+/// distance to the kernel center, and normalized to sum 1.
+pub fn gaussian_kernel_generator_sigma(ksize: usize, sigma: f64) -> Array<f64, Ix2>{
     fn sqr_dis(dx:i32, dy:i32) -> i32{
         dx * dx + dy * dy
     }
+    assert!(sigma > 0.0, "sigma must be positive in gaussian_kernel_generator_sigma().");
+    assert!(ksize % 2 == 1, "ksize must be odd so that the kernel has a center.");
     let cx = (ksize / 2) as i32;
     let cy = (ksize / 2) as i32;
     let mut kernel = Array::zeros((ksize, ksize));
     for x in 0..ksize {
         for y in 0..ksize{
             let dist = sqr_dis(cx - x as i32, cy - y as i32);
-            kernel[[x, y]] = -dist as f64 / 2.0;
+            kernel[[x, y]] = -dist as f64 / (2.0 * sigma * sigma);
         }
     }
     kernel.map_inplace(|x| *x = x.exp());
-    let normalized_constant = kernel.fold(0.0, |acc, x| acc + x);
-    kernel /= normalized_constant;
-    kernel
+    normalize_kernel(&kernel)
+}
+
+/// Generate a derivative-of-Gaussian kernel.
+///
+/// The kernel is the product of 1D Gaussian derivatives of standard deviation
+/// `sigma`: `order_x` is the order along the second axis (the `x` of `sobel()`)
+/// and `order_y` is the order along the first axis. Orders up to 2 are
+/// supported. The Gaussian part is normalized to sum 1. As `sobel()`, first
+/// order kernels are oriented for `filter()` so that increasing intensity
+/// gives positive responses.
+/// # Example
+/// ```
+/// let kernel = simplecv::filter::dog_kernel(7, 1.0, 1, 0);
+/// for i in 0..7 {
+///     for j in 0..7 {
+///         assert!((kernel[[i, j]] + kernel[[i, 6 - j]]).abs() < 1e-12);
+///         assert!((kernel[[i, j]] - kernel[[6 - i, j]]).abs() < 1e-12);
+///     }
+/// }
+/// assert!(kernel[[3, 4]] > 0.0);
+/// ```
+pub fn dog_kernel(ksize: usize, sigma: f64, order_x: u32, order_y: u32) -> Array<f64, Ix2> {
+    assert!(sigma > 0.0, "sigma must be positive in dog_kernel().");
+    assert!(order_x <= 2 && order_y <= 2, "Only orders up to 2 are supported in dog_kernel().");
+    let center = (ksize / 2) as f64;
+    let var = sigma * sigma;
+    let gaussian = Array::from_shape_fn(ksize, |i| (-(i as f64 - center).powi(2) / (2.0 * var)).exp());
+    let gaussian = &gaussian / gaussian.sum();
+    let derivative = |order: u32| -> Array<f64, Ix1> {
+        Array::from_shape_fn(ksize, |i| {
+            let t = i as f64 - center;
+            let factor = match order {
+                0 => 1.0,
+                1 => t / var,
+                _ => t * t / (var * var) - 1.0 / var,
+            };
+            gaussian[i] * factor
+        })
+    };
+    let kx = derivative(order_x);
+    let ky = derivative(order_y);
+    Array::from_shape_fn((ksize, ksize), |(i, j)| ky[i] * kx[j])
+}
+
+/// Gaussian kernels keyed by the kernel size and the bits of sigma.
+type KernelCache = HashMap<(usize, u64), Arc<Array<f64, Ix2>>>;
+
+/// Max number of kernels kept in the Gaussian kernel cache.
+const GAUSSIAN_KERNEL_CACHE_SIZE: usize = 32;
+
+/// Get a Gaussian kernel from `cache`, generating it when missing.
+///
+/// The cache is emptied when it holds `GAUSSIAN_KERNEL_CACHE_SIZE` kernels, so
+/// arbitrary sigmas from users do not pile up.
+fn lookup_gaussian_kernel(cache: &mut KernelCache, ksize: usize, sigma: f64) -> Arc<Array<f64, Ix2>> {
+    let key = (ksize, sigma.to_bits());
+    if !cache.contains_key(&key) && cache.len() >= GAUSSIAN_KERNEL_CACHE_SIZE {
+        cache.clear();
+    }
+    cache.entry(key)
+         .or_insert_with(|| Arc::new(gaussian_kernel_generator_sigma(ksize, sigma)))
+         .clone()
+}
+
+/// Get a Gaussian kernel from the cache shared by the smoothing functions.
+///
+/// Kernels are keyed by `ksize` and `sigma`, so repeated smoothing with the
+/// same parameters does not regenerate them.
+fn cached_gaussian_kernel(ksize: usize, sigma: f64) -> Arc<Array<f64, Ix2>> {
+    static CACHE: OnceLock<Mutex<KernelCache>> = OnceLock::new();
+    let cache = CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+    lookup_gaussian_kernel(&mut cache.lock().unwrap(), ksize, sigma)
+}
+
+/// Get the cached Gaussian kernel converted to the element type `A`.
+fn cached_gaussian_kernel_as<A: Float>(ksize: usize, sigma: f64) -> Array<A, Ix2> {
+    cached_gaussian_kernel(ksize, sigma).mapv(|v| A::from(v).unwrap())
 }
 
 /// Smooth the image with a gaussian kernel.
 ///
-/// The output buffer should be allocated by users.
-/// * `ksize`: is the kernel size. 
+/// The output buffer should be allocated by users. The kernel is the same as
+/// [gaussian_kernel_generator()](./fn.gaussian_kernel_generator.html).
+/// * `ksize`: is the kernel size, which must be odd.
 /// * `border`: how to deal with the border.
-pub fn gaussian_smooth_<S, T>(src: &ArrayBase<S, Ix2>, ksize:usize, border: BorderType, out:&mut ArrayBase<T, Ix2>) 
-    where S: Data<Elem=f64>, T:DataMut<Elem=f64>
+pub fn gaussian_smooth_<A, S, T>(src: &ArrayBase<S, Ix2>, ksize:usize, border: BorderType, out:&mut ArrayBase<T, Ix2>)
+    where A: Float, S: Data<Elem=A>, T:DataMut<Elem=A>
 {
-    let kernel = gaussian_kernel_generator(ksize);
+    let kernel = cached_gaussian_kernel_as(ksize, default_gaussian_sigma(ksize));
     filter_(src, &kernel, border, out);
 }
 
 /// Smooth the image with a gaussian kernel.
 ///
-/// * `ksize`: is the kernel size. 
+/// The kernel is the same as
+/// [gaussian_kernel_generator()](./fn.gaussian_kernel_generator.html).
+/// * `ksize`: is the kernel size, which must be odd so that the kernel is
+///   centered on each pixel. Even sizes panic.
 /// * `border`: how to deal with the border.
-pub fn gaussian_smooth<S>(src: &ArrayBase<S, Ix2>, ksize:usize, border: BorderType) -> Array<f64, Ix2>
-    where S: Data<Elem=f64>
+///
+/// Like [filter()](./fn.filter.html), images of any `Float` type are accepted.
+///
+/// # Example
+/// ```
+/// use simplecv::filter::*;
+/// let img = ndarray::Array::from_shape_fn((8, 8), |(i, j)| ((i * 3 + j * 5) % 7) as f64 / 7.0);
+/// let expected = gaussian_smooth(&img, 5, BorderType::Reflect);
+/// let output = gaussian_smooth(&img.mapv(|v| v as f32), 5, BorderType::Reflect);
+/// for (a, b) in output.iter().zip(expected.iter()) {
+///     assert!((f64::from(*a) - b).abs() < 1e-6);
+/// }
+/// ```
+///
+/// ```should_panic
+/// use simplecv::filter::*;
+/// let img = ndarray::Array::<f64, _>::zeros((5, 5));
+/// // an even kernel size has no center
+/// gaussian_smooth(&img, 4, BorderType::Reflect);
+/// ```
+pub fn gaussian_smooth<A, S>(src: &ArrayBase<S, Ix2>, ksize:usize, border: BorderType) -> Array<A, Ix2>
+    where A: Float, S: Data<Elem=A>
 {
-    let kernel = gaussian_kernel_generator(ksize);
+    let kernel = cached_gaussian_kernel_as(ksize, default_gaussian_sigma(ksize));
     filter(src, &kernel, border)
 }
 
+/// Smooth the image with a gaussian kernel of standard deviation `sigma`.
+///
+/// * `ksize`: is the kernel size, which must be odd.
+/// * `sigma`: is the standard deviation of the Gaussian.
+/// * `border`: how to deal with the border.
+pub fn gaussian_smooth_sigma<S>(src: &ArrayBase<S, Ix2>, ksize: usize, sigma: f64, border: BorderType) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    let kernel = cached_gaussian_kernel(ksize, sigma);
+    filter(src, &*kernel, border)
+}
+
+/// Smooth the image with a precomputed kernel.
+///
+/// This is the same as [filter()](./fn.filter.html), but it checks that the
+/// kernel sizes are odd so that the kernel is centered on each pixel. Use it to
+/// avoid regenerating the kernel in tight loops.
+/// * `kernel`: the smoothing kernel, usually normalized to sum 1.
+/// * `border`: how to deal with the border.
+pub fn smooth_with_kernel<S, K>(src: &ArrayBase<S, Ix2>, kernel: &ArrayBase<K, Ix2>, border: BorderType) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>, K: Data<Elem=f64>
+{
+    assert!(kernel.shape()[0] % 2 == 1 && kernel.shape()[1] % 2 == 1,
+            "Kernel sizes must be odd in smooth_with_kernel().");
+    filter(src, kernel, border)
+}
+
+/// Smooth the image with a Gaussian kernel owned by the caller.
+///
+/// `kernel` is usually generated once by
+/// [gaussian_kernel_generator()](./fn.gaussian_kernel_generator.html) or
+/// [gaussian_kernel_generator_sigma()](./fn.gaussian_kernel_generator_sigma.html)
+/// and reused across calls.
+///
+/// # Example
+/// ```
+/// use simplecv::filter::*;
+/// let img = ndarray::Array::from_shape_fn((9, 9), |(i, j)| ((i * 3 + j * 5) % 7) as f64 / 7.0);
+/// let kernel = gaussian_kernel_generator(5);
+/// for _ in 0..3 {
+///     assert_eq!(gaussian_smooth_cached(&img, &kernel, BorderType::Reflect),
+///                gaussian_smooth(&img, 5, BorderType::Reflect));
+/// }
+/// ```
+pub fn gaussian_smooth_cached<S>(src: &ArrayBase<S, Ix2>, kernel: &Array<f64, Ix2>, border: BorderType) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    smooth_with_kernel(src, kernel, border)
+}
+
+/// Generate a normalized `kh` x `kw` mean kernel.
+fn mean_kernel(kh: usize, kw: usize) -> Array<f64, Ix2> {
+    assert!(kh % 2 == 1 && kw % 2 == 1, "Kernel sizes must be odd so that the kernel has a center.");
+    normalize_kernel(&Array::ones((kh, kw)))
+}
+
 /// Smooth the image with a mean kernel.
 ///
 /// The output buffer should be allocated by users.
-/// * `ksize`: is the kernel size. 
+/// * `ksize`: is the kernel size, which must be odd.
 /// * `border`: how to deal with the border.
 pub fn mean_smooth_<S, T>(src: &ArrayBase<S, Ix2>, ksize:usize, border:BorderType, out: &mut ArrayBase<T, Ix2>)
     where S: Data<Elem=f64>, T:DataMut<Elem=f64>
 {
-    let kernel = Array::ones((ksize, ksize)) / ((ksize * ksize) as f64);
+    let kernel = mean_kernel(ksize, ksize);
     filter_(src, &kernel, border, out);
 }
 
 /// Smooth the image with a mean kernel.
 ///
-/// * `ksize`: is the kernel size. 
+/// * `ksize`: is the kernel size, which must be odd.
 /// * `border`: how to deal with the border.
 ///
 /// # Example
@@ -212,10 +651,139 @@ pub fn mean_smooth_<S, T>(src: &ArrayBase<S, Ix2>, ksize:usize, border:BorderTyp
 pub fn mean_smooth<S>(src: &ArrayBase<S, Ix2>, ksize: usize, border:BorderType) -> Array<f64, Ix2>
     where S: Data<Elem=f64>
 {
-    let kernel = Array::ones((ksize, ksize)) / ((ksize * ksize) as f64);
+    let kernel = mean_kernel(ksize, ksize);
     filter(src, &kernel, border)
 }
 
+/// Smooth the image with a `kh` x `kw` mean kernel.
+///
+/// A rectangular window averages more along one axis than the other, e.g.
+/// `kh = 1` averages only horizontally.
+/// * `kh`: is the kernel height.
+/// * `kw`: is the kernel width.
+/// * `border`: how to deal with the border.
+///
+/// # Example
+/// ```
+/// use simplecv::filter::*;
+/// let img = ndarray::Array::from_shape_fn((5, 9), |(i, j)| if i == 2 || j == 4 { 1.0 } else { 0.0 });
+/// let smoothed = mean_smooth_rect(&img, 1, 5, BorderType::Constant(0.0));
+/// // the horizontal line is kept, and the vertical line is spread horizontally
+/// assert!(smoothed.row(2).iter().skip(2).take(5).all(|&v| (v - 1.0).abs() < 1e-12));
+/// assert!(smoothed.row(0).iter().skip(2).take(5).all(|&v| (v - 0.2).abs() < 1e-12));
+/// assert_eq!(smoothed[[0, 1]], 0.0);
+/// ```
+pub fn mean_smooth_rect<S>(src: &ArrayBase<S, Ix2>, kh: usize, kw: usize, border: BorderType) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    let kernel = mean_kernel(kh, kw);
+    filter(src, &kernel, border)
+}
+
+/// Simulate linear camera motion with a line-shaped averaging kernel.
+///
+/// The kernel is a `length` x `length` array with a line of `length` pixels
+/// through its center at `angle_deg` degrees counterclockwise from the `x`
+/// direction (the second axis). The kernel is normalized to sum 1, so the
+/// brightness is kept.
+/// * `length`: the length of the motion in pixels, which should be odd.
+/// * `angle_deg`: the direction of the motion in degrees.
+/// * `border`: how to deal with the border.
+///
+/// # Example
+/// ```
+/// use simplecv::filter::*;
+/// let img = ndarray::Array::from_shape_fn((8, 8), |(i, j)| ((i * 5 + j * 3) % 7) as f64 / 7.0);
+/// let blurred = motion_blur(&img, 5, 0.0, BorderType::Reflect);
+/// let expected = mean_smooth_rect(&img, 1, 5, BorderType::Reflect);
+/// assert!(simplecv::utils::max_diff(&blurred, &expected) < 1e-12);
+/// ```
+pub fn motion_blur<S>(src: &ArrayBase<S, Ix2>, length: usize, angle_deg: f64, border: BorderType) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    assert!(length % 2 == 1, "length must be odd in motion_blur().");
+    let center = (length / 2) as f64;
+    let (sin, cos) = angle_deg.to_radians().sin_cos();
+    let mut kernel = Array::zeros((length, length));
+    for step in 0..length {
+        let t = step as f64 - center;
+        let row = (center - t * sin).round() as usize;
+        let col = (center + t * cos).round() as usize;
+        kernel[[row, col]] = 1.0;
+    }
+    filter(src, &normalize_kernel(&kernel), border)
+}
+
+/// Compute the local variance of each pixel over a `ksize` x `ksize` window.
+///
+/// The output buffer should be allocated by users.
+/// * `ksize`: is the window size.
+/// * `border`: how to deal with the border.
+pub fn local_variance_<S, T>(src: &ArrayBase<S, Ix2>, ksize: usize, border: BorderType, out: &mut ArrayBase<T, Ix2>)
+    where S: Data<Elem=f64>, T: DataMut<Elem=f64>
+{
+    let height = src.shape()[0];
+    let width = src.shape()[1];
+    // pad the image by the window radius, so every window is inside the integral images
+    let r = (ksize / 2) as i32;
+    let padded = Array::from_shape_fn((height + ksize - 1, width + ksize - 1), |(i, j)| {
+        access_img_border(src, i as i32 - r, j as i32 - r, border)
+    });
+    let sum = super::utils::integral_image(&padded);
+    let sqr_sum = super::utils::integral_image_sq(&padded);
+    let rect = |t: &Array<f64, Ix2>, i: usize, j: usize| {
+        t[[i + ksize, j + ksize]] - t[[i, j + ksize]] - t[[i + ksize, j]] + t[[i, j]]
+    };
+    let n = (ksize * ksize) as f64;
+    for i in 0..height {
+        for j in 0..width {
+            let mean = rect(&sum, i, j) / n;
+            let sqr_mean = rect(&sqr_sum, i, j) / n;
+            // E[x^2] - E[x]^2, clipped to avoid tiny negative values caused by rounding
+            out[[i, j]] = (sqr_mean - mean * mean).max(0.0);
+        }
+    }
+}
+
+/// Compute the local variance of each pixel over a `ksize` x `ksize` window.
+///
+/// The variance is computed by `E[x^2] - E[x]^2` with the integral images of
+/// the values and their squares, so the cost does not depend on `ksize`. It
+/// can be used to measure the texture or the focus of a region.
+/// * `ksize`: is the window size.
+/// * `border`: how to deal with the border.
+///
+/// # Example
+/// ```
+/// use simplecv::filter::*;
+/// let mut img = ndarray::Array::from_elem((6, 6), 0.5);
+/// // put some noise on the right part
+/// for i in 0..6 {
+///     img[[i, 4]] = 0.1;
+///     img[[i, 5]] = 0.9;
+/// }
+/// let var = local_variance(&img, 3, BorderType::Replicate);
+/// assert!(var[[2, 1]].abs() < 1e-9);
+/// assert!(var[[2, 4]] > 0.01);
+///
+/// // the same as E[x^2] - E[x]^2 with two mean filters
+/// let mean = mean_smooth(&img, 5, BorderType::Reflect);
+/// let sqr_mean = mean_smooth(&img.mapv(|x| x * x), 5, BorderType::Reflect);
+/// let expected = (sqr_mean - mean.mapv(|x| x * x)).mapv(|x| x.max(0.0));
+/// let var = local_variance(&img, 5, BorderType::Reflect);
+/// assert!(simplecv::utils::max_diff(&var, &expected) < 1e-9);
+/// ```
+pub fn local_variance<S>(src: &ArrayBase<S, Ix2>, ksize: usize, border: BorderType) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    let mut buffer = Array::zeros((src.shape()[0], src.shape()[1]));
+    local_variance_(src, ksize, border, &mut buffer);
+    buffer
+}
+
+/// The 3x3 Sobel kernel of `dx=1`.
+const SOBEL_KERNEL: [[f64; 3]; 3] = [[-1.0, 0.0, 1.0], [-2.0, 0.0, 2.0], [-1.0, 0.0, 1.0]];
+
 /// Sobel operator implementation.
 ///
 /// The output buffer should be allocated by users.
@@ -233,7 +801,7 @@ pub fn sobel_<S, T>(src: &ArrayBase<S, Ix2>, ksize: usize, dx: u32, dy: u32, bor
 {
     assert!(ksize==3, "Only ksize=3 is supported in sobel_() now.");
     assert!(dx + dy == 1, "Only first order gradient of one direction is supported in sobel_() now.");
-    let kernel = arr2(&[[-1.0, 0.0, 1.0], [-2.0, 0.0, 2.0], [-1.0, 0.0, 1.0]]);
+    let kernel = aview2(&SOBEL_KERNEL);
     if dx == 1 {
         filter_(src, &kernel, border, out);
     } else{
@@ -259,9 +827,155 @@ pub fn sobel<S>(src: &ArrayBase<S, Ix2>, ksize: usize, dx: u32, dy: u32, border:
     buffer
 }
 
+/// Sobel operator with a scale and an offset on the output.
+///
+/// The output is `scale * sobel(src, ksize, dx, dy, border) + delta`, which is
+/// useful to map gradient responses into a displayable range.
+/// * `scale`: the multiplier of the Sobel response.
+/// * `delta`: the offset added after scaling.
+///
+/// # Example
+/// ```
+/// use simplecv::filter::*;
+/// // a ramp whose Sobel response is 0.8 inside the image
+/// let ramp = ndarray::Array::from_shape_fn((5, 5), |(_, j)| j as f64 * 0.1);
+/// let out = sobel_scaled(&ramp, 3, 1, 0, 0.5, 0.5, BorderType::Replicate);
+/// assert!((out[[2, 2]] - 0.9).abs() < 1e-9);
+/// assert!(out.iter().all(|v| *v >= 0.0 && *v <= 1.0));
+/// ```
+pub fn sobel_scaled<S>(src: &ArrayBase<S, Ix2>, ksize: usize, dx: u32, dy: u32, scale: f64, delta: f64,
+                       border: BorderType) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    let mut buffer = sobel(src, ksize, dx, dy, border);
+    buffer.mapv_inplace(|v| scale * v + delta);
+    buffer
+}
+
+/// Detect horizontal edges, i.e. intensity changes along the first axis.
+///
+/// This is the absolute value of `sobel(src, 3, 0, 1, border)`.
+/// # Example
+/// ```
+/// use simplecv::filter::*;
+/// // a horizontal step between the second and the third rows
+/// let horizontal = ndarray::Array::from_shape_fn((5, 5), |(i, _)| if i < 2 { 1.0 } else { 0.0 });
+/// let edges = horizontal_edges(&horizontal, BorderType::Replicate);
+/// assert_eq!(edges[[1, 2]], 4.0);
+/// assert_eq!(edges[[4, 2]], 0.0);
+/// // the transposed image has a vertical step, to which it does not respond
+/// let vertical = horizontal.t().to_owned();
+/// assert!(horizontal_edges(&vertical, BorderType::Replicate).iter().all(|&v| v == 0.0));
+/// assert_eq!(vertical_edges(&vertical, BorderType::Replicate), edges.t());
+/// ```
+pub fn horizontal_edges<S>(src: &ArrayBase<S, Ix2>, border: BorderType) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    let mut buffer = sobel(src, 3, 0, 1, border);
+    buffer.mapv_inplace(f64::abs);
+    buffer
+}
+
+/// Detect vertical edges, i.e. intensity changes along the second axis.
+///
+/// This is the absolute value of `sobel(src, 3, 1, 0, border)`.
+pub fn vertical_edges<S>(src: &ArrayBase<S, Ix2>, border: BorderType) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    let mut buffer = sobel(src, 3, 1, 0, border);
+    buffer.mapv_inplace(f64::abs);
+    buffer
+}
+
+/// Laplacian operator implementation.
+///
+/// The output buffer should be allocated by users.
+///
+/// The 3x3 kernel `[[0, 1, 0], [1, -4, 1], [0, 1, 0]]` is applied, which is the
+/// same as OpenCV `Laplacian()` with `ksize=1`.
+/// * `border`: border type.
+pub fn laplacian_<S, T>(src: &ArrayBase<S, Ix2>, border: BorderType, out: &mut ArrayBase<T, Ix2>)
+    where S: Data<Elem=f64>, T: DataMut<Elem=f64>
+{
+    let kernel = arr2(&[[0.0, 1.0, 0.0], [1.0, -4.0, 1.0], [0.0, 1.0, 0.0]]);
+    filter_(src, &kernel, border, out);
+}
+
+/// Laplacian operator implementation.
+///
+/// The 3x3 kernel `[[0, 1, 0], [1, -4, 1], [0, 1, 0]]` is applied, which is the
+/// same as OpenCV `Laplacian()` with `ksize=1`.
+/// * `border`: border type.
+pub fn laplacian<S>(src: &ArrayBase<S, Ix2>, border: BorderType) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    let mut buffer = Array::zeros((src.shape()[0], src.shape()[1]));
+    laplacian_(src, border, &mut buffer);
+    buffer
+}
+
+/// Compute the spatial and temporal gradients of two frames.
+///
+/// Return `(Ix, Iy, It)`, which are the inputs of optical flow methods like
+/// Lucas-Kanade. `Ix` and `Iy` are the Sobel gradients (`dx=1` and `dy=1`) of the
+/// average of the two frames, divided by 8 so that they approximate the
+/// derivatives per pixel. `It` is `frame_b - frame_a`.
+/// * `border`: border type.
+///
+/// # Example
+/// ```
+/// use simplecv::filter::*;
+/// let frame_a = ndarray::Array::from_shape_fn((8, 8), |(_, j)| if j < 4 { 0.0 } else { 1.0 });
+/// let frame_b = ndarray::Array::from_shape_fn((8, 8), |(_, j)| if j < 5 { 0.0 } else { 1.0 });
+/// let (ix, iy, it) = spatial_temporal_gradients(&frame_a, &frame_b, BorderType::Replicate);
+/// assert!(it[[3, 4]] < 0.0);
+/// assert_eq!(it[[3, 1]], 0.0);
+/// assert!(ix[[3, 4]] > 0.0);
+/// assert_eq!(iy[[3, 4]], 0.0);
+/// ```
+pub fn spatial_temporal_gradients<S, T>(frame_a: &ArrayBase<S, Ix2>, frame_b: &ArrayBase<T, Ix2>, border: BorderType)
+    -> (Array<f64, Ix2>, Array<f64, Ix2>, Array<f64, Ix2>)
+    where S: Data<Elem=f64>, T: Data<Elem=f64>
+{
+    assert_eq!(frame_a.shape(), frame_b.shape(), "Frames must have the same shape in spatial_temporal_gradients().");
+    let average = (frame_a + frame_b) / 2.0;
+    let ix = sobel(&average, 3, 1, 0, border) / 8.0;
+    let iy = sobel(&average, 3, 0, 1, border) / 8.0;
+    let it = frame_b - frame_a;
+    (ix, iy, it)
+}
+
+/// Compute the directional derivative of the image at angle `theta`.
+///
+/// The gradients `Gx` and `Gy` are computed with derivative-of-Gaussian
+/// kernels of standard deviation `sigma` (see [dog_kernel()](./fn.dog_kernel.html)),
+/// and combined as `cos(theta) * Gx + sin(theta) * Gy`. `theta` is in radians
+/// and `theta = 0` is the `x` direction of `sobel()`.
+/// # Example
+/// ```
+/// use simplecv::filter::{steerable_gradient, BorderType};
+/// // a grating whose gradient is along the x direction
+/// let img = ndarray::Array::from_shape_fn((24, 24), |(_, j)| (j as f64 * 0.5).sin());
+/// let energy = |theta: f64| steerable_gradient(&img, 1.5, theta, BorderType::Reflect)
+///                             .mapv(|v| v * v).sum();
+/// let best = (0..12).map(|k| k as f64 * std::f64::consts::PI / 12.0)
+///                   .max_by(|a, b| energy(*a).partial_cmp(&energy(*b)).unwrap())
+///                   .unwrap();
+/// assert_eq!(best, 0.0);
+/// ```
+pub fn steerable_gradient<S>(src: &ArrayBase<S, Ix2>, sigma: f64, theta: f64, border: BorderType) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    let ksize = 2 * (3.0 * sigma).ceil() as usize + 1;
+    let gx = filter(src, &dog_kernel(ksize, sigma, 1, 0), border);
+    let gy = filter(src, &dog_kernel(ksize, sigma, 0, 1), border);
+    gx * theta.cos() + gy * theta.sin()
+}
+
 /// Get the norm of image processed by a Sobel operation.
 ///
-/// Currently `norm=-1, 1, 2` are supported, where -1 means the infinty norm (max of absolute value). 
+/// Any `norm=p` with `p >= 1` computes `(|gx|^p + |gy|^p)^(1/p)`, and `norm=-1`
+/// means the infinity norm (max of absolute value). `p=1` and `p=2` have fast paths.
 /// This function can be used to obtain the edge of original image.
 ///
 /// This function is implemented by `sobel()`. First order derivative of x and y direction are 
@@ -269,7 +983,23 @@ pub fn sobel<S>(src: &ArrayBase<S, Ix2>, ksize: usize, dx: u32, dy: u32, border:
 /// * `ksize`: the kernel size.
 /// * `norm`: the norm used for computation.
 /// * `border`: border type.
-
+///
+/// # Example
+/// ```
+/// use simplecv::filter::*;
+/// let img = ndarray::Array::from_shape_fn((6, 6), |(i, j)| ((i * 3 + j * 5) % 7) as f64 / 7.0);
+/// let gx = sobel(&img, 3, 1, 0, BorderType::Reflect);
+/// let gy = sobel(&img, 3, 0, 1, BorderType::Reflect);
+/// let l2 = sobel_norm(&img, 3, 2, BorderType::Reflect);
+/// let l3 = sobel_norm(&img, 3, 3, BorderType::Reflect);
+/// let linf = sobel_norm(&img, 3, -1, BorderType::Reflect);
+/// for ((i, j), v) in l2.indexed_iter() {
+///     let (a, b) = (gx[[i, j]].abs(), gy[[i, j]].abs());
+///     assert!((v - (a * a + b * b).sqrt()).abs() < 1e-12);
+///     assert!((l3[[i, j]] - (a.powi(3) + b.powi(3)).cbrt()).abs() < 1e-12);
+///     assert!(linf[[i, j]] <= l3[[i, j]] + 1e-12 && l3[[i, j]] <= v + 1e-12);
+/// }
+/// ```
 pub fn sobel_norm<S>(src: &ArrayBase<S, Ix2>, ksize: usize, norm: i32, border: BorderType) -> Array<f64, Ix2>
     where S: Data<Elem=f64>
 {
@@ -290,13 +1020,375 @@ pub fn sobel_norm<S>(src: &ArrayBase<S, Ix2>, ksize: usize, norm: i32, border: B
             }
             buffer
         }
+        p if p > 2 => {
+            let t = gx.mapv(|x| x.abs().powi(p)) + gy.mapv(|x| x.abs().powi(p));
+            t.mapv(|x| x.powf(1.0 / p as f64))
+        }
         _ => {
-            panic!(format!("Norm = {} is not supported by sobel_norm()!", norm));
+            panic!("Norm = {} is not supported by sobel_norm()!", norm);
         }
     };
     gnorm
 }
 
+/// Anisotropic diffusion (Perona-Malik) smoothing.
+///
+/// The image is diffused for `iterations` steps with the conductance function
+/// `g(d) = exp(-(d / kappa)^2)`, where `d` is the difference to one of the four
+/// neighbors. Therefore regions are smoothed while edges with a contrast much
+/// larger than `kappa` are preserved. The border is handled as `Replicate`, so
+/// there is no flux across the border.
+///
+/// * `iterations`: the number of diffusion steps.
+/// * `kappa`: the gradient threshold of the conductance function.
+/// * `lambda`: the step size. It should be in (0, 0.25] for the four-neighbor
+///   scheme to be stable.
+///
+/// # Example
+/// ```
+/// use simplecv::filter::anisotropic_diffusion;
+/// // a step edge with alternating noise on both sides
+/// let img = ndarray::Array::from_shape_fn((8, 8), |(i, j)| {
+///     let base = if j < 4 { 0.2 } else { 0.8 };
+///     base + if (i + j) % 2 == 0 { 0.02 } else { -0.02 }
+/// });
+/// let out = anisotropic_diffusion(&img, 10, 0.1, 0.2);
+/// // the contrast of the edge is preserved
+/// assert!(out[[4, 4]] - out[[4, 3]] > 0.5);
+/// // the noise on the flat region is reduced
+/// assert!((out[[4, 0]] - out[[4, 1]]).abs() < 0.01);
+/// ```
+pub fn anisotropic_diffusion<S>(src: &ArrayBase<S, Ix2>, iterations: usize, kappa: f64, lambda: f64) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    assert!(kappa > 0.0, "kappa must be positive in anisotropic_diffusion().");
+    assert!(lambda > 0.0 && lambda <= 0.25, "lambda must be in (0, 0.25] in anisotropic_diffusion().");
+    let height = src.shape()[0];
+    let width = src.shape()[1];
+    let conductance = |d: f64| (-(d / kappa).powi(2)).exp();
+    let mut current = src.to_owned();
+    let mut next = Array::zeros((height, width));
+    for _ in 0..iterations {
+        for i in 0..height {
+            for j in 0..width {
+                let v = current[[i, j]];
+                let mut flux = 0.0;
+                for (dx, dy) in Connectivity::Four.offsets() {
+                    let nv = access_img_border(&current, i as i32 + dx, j as i32 + dy, BorderType::Replicate);
+                    flux += conductance(nv - v) * (nv - v);
+                }
+                next[[i, j]] = v + lambda * flux;
+            }
+        }
+        std::mem::swap(&mut current, &mut next);
+    }
+    current
+}
+
+/// Guided filter.
+///
+/// Implemented following [He et al., Guided Image Filtering](http://kaiminghe.com/publications/eccv10guidedfilter.pdf).
+/// The output is locally a linear transform of `guide`, so the edges of `guide`
+/// are preserved. When `guide` is `src` itself, it works as an edge-preserving
+/// smoother. Local means and variances are computed with mean filters of size
+/// `2 * radius + 1` and the `Replicate` border.
+///
+/// * `guide`: the guidance image, which must have the same shape as `src`.
+/// * `radius`: the radius of the local window.
+/// * `eps`: the regularization. Larger `eps` gives smoother result.
+///
+/// # Example
+/// ```
+/// use simplecv::filter::*;
+/// let img = ndarray::Array::from_shape_fn((8, 8), |(_, j)| if j < 4 { 0.2 } else { 0.8 });
+/// let out = guided_filter(&img, &img, 2, 1e-4);
+/// assert!(simplecv::utils::max_diff(&out, &img) < 1e-2);
+/// let blurred = mean_smooth(&img, 5, BorderType::Reflect);
+/// assert!(out[[4, 4]] - out[[4, 3]] > blurred[[4, 4]] - blurred[[4, 3]]);
+/// ```
+pub fn guided_filter<S, G>(src: &ArrayBase<S, Ix2>, guide: &ArrayBase<G, Ix2>, radius: usize, eps: f64) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>, G: Data<Elem=f64>
+{
+    assert_eq!(src.shape(), guide.shape(), "guide must have the same shape as src in guided_filter().");
+    let ksize = 2 * radius + 1;
+    let border = BorderType::Replicate;
+    let mean_i = mean_smooth(guide, ksize, border);
+    let mean_p = mean_smooth(src, ksize, border);
+    let corr_ii = mean_smooth(&(guide * guide), ksize, border);
+    let corr_ip = mean_smooth(&(guide * src), ksize, border);
+    let var_i = corr_ii - &mean_i * &mean_i;
+    let cov_ip = corr_ip - &mean_i * &mean_p;
+    let a = cov_ip / var_i.mapv(|v| v + eps);
+    let b = mean_p - &a * &mean_i;
+    let mean_a = mean_smooth(&a, ksize, border);
+    let mean_b = mean_smooth(&b, ksize, border);
+    mean_a * guide + mean_b
+}
+
+/// A linear filter with a precomputed kernel.
+///
+/// Reapplying the same filter to many images, e.g. the frames of a video, with
+/// `Filter` avoids generating the kernel for every image.
+///
+/// # Example
+/// ```
+/// use simplecv::filter::*;
+/// let img = ndarray::Array::from_shape_fn((6, 6), |(i, j)| ((i * 5 + j) % 7) as f64 / 7.0);
+/// let blur = Filter::gaussian(5, 1.5, BorderType::Reflect);
+/// let expected = gaussian_smooth_sigma(&img, 5, 1.5, BorderType::Reflect);
+/// assert_eq!(blur.apply(&img), expected);
+/// let mut out = ndarray::Array::zeros((6, 6));
+/// blur.apply_into(&img, &mut out);
+/// assert_eq!(out, expected);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Filter {
+    kernel: Array<f64, Ix2>,
+    border: BorderType
+}
+
+impl Filter {
+    /// Create a filter with the given kernel and border type.
+    pub fn new(kernel: Array<f64, Ix2>, border: BorderType) -> Filter {
+        Filter { kernel, border }
+    }
+
+    /// Create a Gaussian filter of `ksize` x `ksize` with standard deviation `sigma`.
+    pub fn gaussian(ksize: usize, sigma: f64, border: BorderType) -> Filter {
+        Filter::new(gaussian_kernel_generator_sigma(ksize, sigma), border)
+    }
+
+    /// Create a mean filter of `ksize` x `ksize`.
+    pub fn mean(ksize: usize, border: BorderType) -> Filter {
+        Filter::new(mean_kernel(ksize, ksize), border)
+    }
+
+    /// The kernel of this filter.
+    pub fn kernel(&self) -> &Array<f64, Ix2> {
+        &self.kernel
+    }
+
+    /// Apply the filter to the source image.
+    pub fn apply<S>(&self, src: &ArrayBase<S, Ix2>) -> Array<f64, Ix2>
+        where S: Data<Elem=f64>
+    {
+        filter(src, &self.kernel, self.border)
+    }
+
+    /// Apply the filter to the source image. The output buffer is allocated by users.
+    pub fn apply_into<S, T>(&self, src: &ArrayBase<S, Ix2>, out: &mut ArrayBase<T, Ix2>)
+        where S: Data<Elem=f64>, T: DataMut<Elem=f64>
+    {
+        filter_(src, &self.kernel, self.border, out);
+    }
+}
+
+/// An operation mapping a 2D image to another one of the same shape.
+///
+/// It allows composing different operations, e.g. as `Vec<Box<dyn ImageOp>>`.
+///
+/// # Example
+/// ```
+/// use simplecv::filter::*;
+/// let img = ndarray::Array::from_shape_fn((8, 8), |(i, j)| ((i * 3 + j * 5) % 7) as f64 / 7.0);
+/// let ops: Vec<Box<dyn ImageOp>> = vec![Box::new(Filter::gaussian(3, 1.0, BorderType::Reflect)),
+///                                       Box::new(Sobel::new(1, 0, BorderType::Reflect))];
+/// let mut result = img.clone();
+/// for op in &ops {
+///     result = op.apply(&result.view());
+/// }
+/// let blurred = gaussian_smooth_sigma(&img, 3, 1.0, BorderType::Reflect);
+/// let expected = sobel(&blurred, 3, 1, 0, BorderType::Reflect);
+/// assert_eq!(result, expected);
+/// ```
+pub trait ImageOp {
+    /// Apply the operation to the source image. The output buffer is allocated by users.
+    fn apply_into(&self, src: &ArrayView2<f64>, out: &mut ArrayViewMut2<f64>);
+
+    /// Apply the operation to the source image.
+    fn apply(&self, src: &ArrayView2<f64>) -> Array<f64, Ix2> {
+        let mut buffer = Array::zeros((src.shape()[0], src.shape()[1]));
+        self.apply_into(src, &mut buffer.view_mut());
+        buffer
+    }
+}
+
+impl ImageOp for Filter {
+    fn apply_into(&self, src: &ArrayView2<f64>, out: &mut ArrayViewMut2<f64>) {
+        filter_(src, &self.kernel, self.border, out);
+    }
+}
+
+/// The 3x3 Sobel operator as an [ImageOp](./trait.ImageOp.html).
+///
+/// Only `dx=1, dy=0` and `dx=0, dy=1` are supported, the same as `sobel()`.
+#[derive(Copy, Clone, Debug)]
+pub struct Sobel {
+    dx: u32,
+    dy: u32,
+    border: BorderType
+}
+
+impl Sobel {
+    /// Create a Sobel operator of the derivative orders `dx` and `dy`.
+    pub fn new(dx: u32, dy: u32, border: BorderType) -> Sobel {
+        assert!(dx + dy == 1, "Only first order gradient of one direction is supported in Sobel.");
+        Sobel { dx, dy, border }
+    }
+}
+
+impl ImageOp for Sobel {
+    fn apply_into(&self, src: &ArrayView2<f64>, out: &mut ArrayViewMut2<f64>) {
+        sobel_(src, 3, self.dx, self.dy, self.border, out);
+    }
+}
+
+/// A sequence of [ImageOp](./trait.ImageOp.html)s applied one after another.
+///
+/// The intermediate results are written into two buffers owned by the
+/// pipeline, which are reused when running on images of the same shape, e.g.
+/// the frames of a video.
+///
+/// # Example
+/// ```
+/// use simplecv::filter::*;
+/// let frame = ndarray::Array::from_shape_fn((8, 8), |(i, j)| ((i * 3 + j * 5) % 7) as f64 / 7.0);
+/// let mut pipeline = Pipeline::new().then(Filter::gaussian(3, 1.0, BorderType::Reflect))
+///                                   .then(Sobel::new(0, 1, BorderType::Reflect));
+/// let blurred = gaussian_smooth_sigma(&frame, 3, 1.0, BorderType::Reflect);
+/// let expected = sobel(&blurred, 3, 0, 1, BorderType::Reflect);
+/// assert_eq!(pipeline.run(&frame), expected);
+/// assert_eq!(pipeline.run(&frame), expected);
+/// ```
+#[derive(Default)]
+pub struct Pipeline {
+    ops: Vec<Box<dyn ImageOp>>,
+    buffers: [Array<f64, Ix2>; 2]
+}
+
+impl Pipeline {
+    /// Create an empty pipeline.
+    pub fn new() -> Pipeline {
+        Pipeline::default()
+    }
+
+    /// Append an operation to the pipeline.
+    pub fn then<O: ImageOp + 'static>(mut self, op: O) -> Pipeline {
+        self.ops.push(Box::new(op));
+        self
+    }
+
+    /// Apply all operations in order to the source image.
+    pub fn run<S>(&mut self, src: &ArrayBase<S, Ix2>) -> Array<f64, Ix2>
+        where S: Data<Elem=f64>
+    {
+        if self.ops.is_empty() {
+            return src.to_owned();
+        }
+        self.run_into(src).clone()
+    }
+
+    /// Apply all operations in order to the source image.
+    ///
+    /// The result is kept in a buffer of the pipeline and returned by
+    /// reference. It is overwritten by the next run.
+    pub fn run_into<S>(&mut self, src: &ArrayBase<S, Ix2>) -> &Array<f64, Ix2>
+        where S: Data<Elem=f64>
+    {
+        let shape = (src.shape()[0], src.shape()[1]);
+        for buffer in self.buffers.iter_mut() {
+            if buffer.dim() != shape {
+                *buffer = Array::zeros(shape);
+            }
+        }
+        let (first, second) = self.buffers.split_at_mut(1);
+        let (mut input, mut output) = (&mut second[0], &mut first[0]);
+        match self.ops.split_first() {
+            None => output.assign(src),
+            Some((head, tail)) => {
+                head.apply_into(&src.view(), &mut output.view_mut());
+                for op in tail {
+                    std::mem::swap(&mut input, &mut output);
+                    op.apply_into(&input.view(), &mut output.view_mut());
+                }
+            }
+        }
+        output
+    }
+}
+
+/// A [Pipeline](./struct.Pipeline.html) for frames of a fixed shape.
+///
+/// The intermediate buffers are allocated on construction and the result is
+/// returned by reference. With the built-in `Filter` and `Sobel` operations,
+/// processing a frame does not allocate.
+///
+/// # Example
+/// ```
+/// use simplecv::filter::*;
+/// let mut processor = FrameProcessor::new((8, 8)).then(Filter::gaussian(3, 1.0, BorderType::Reflect))
+///                                                .then(Sobel::new(1, 0, BorderType::Reflect));
+/// for t in 0..4 {
+///     let frame = ndarray::Array::from_shape_fn((8, 8), |(i, j)| ((i * 3 + j * 5 + t) % 7) as f64 / 7.0);
+///     let blurred = gaussian_smooth_sigma(&frame, 3, 1.0, BorderType::Reflect);
+///     let expected = sobel(&blurred, 3, 1, 0, BorderType::Reflect);
+///     assert_eq!(processor.process(&frame.view()), &expected);
+/// }
+/// ```
+pub struct FrameProcessor {
+    pipeline: Pipeline
+}
+
+impl FrameProcessor {
+    /// Create an empty processor for frames of the shape `(height, width)`.
+    pub fn new(shape: (usize, usize)) -> FrameProcessor {
+        let pipeline = Pipeline { ops: Vec::new(), buffers: [Array::zeros(shape), Array::zeros(shape)] };
+        FrameProcessor { pipeline }
+    }
+
+    /// Append an operation to the processor.
+    pub fn then<O: ImageOp + 'static>(mut self, op: O) -> FrameProcessor {
+        self.pipeline = self.pipeline.then(op);
+        self
+    }
+
+    /// Apply all operations in order to a frame.
+    ///
+    /// The frame must have the shape given on construction. The returned
+    /// buffer is overwritten by the next call.
+    pub fn process(&mut self, frame: &ArrayView2<f64>) -> &Array<f64, Ix2> {
+        assert!(frame.dim() == self.pipeline.buffers[0].dim(), "The frame shape does not match in FrameProcessor::process().");
+        self.pipeline.run_into(frame)
+    }
+}
+
 pub mod canny;
 pub use canny::canny_edge;
 pub use canny::canny_edge_;
+pub use canny::estimate_thresholds;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cached_gaussian_kernel_matches_fresh() {
+        for &(ksize, sigma) in &[(3, 1.0), (5, 1.0), (5, 2.5), (7, 0.8)] {
+            let fresh = gaussian_kernel_generator_sigma(ksize, sigma);
+            let kernel = cached_gaussian_kernel(ksize, sigma);
+            assert_eq!(*kernel, fresh);
+            // the second call hits the cache and shares the same kernel
+            assert!(Arc::ptr_eq(&cached_gaussian_kernel(ksize, sigma), &kernel));
+        }
+    }
+
+    #[test]
+    fn cached_gaussian_kernel_is_bounded() {
+        let mut cache = KernelCache::new();
+        for i in 0..(3 * GAUSSIAN_KERNEL_CACHE_SIZE) {
+            let sigma = 1.0 + i as f64 * 0.01;
+            assert_eq!(*lookup_gaussian_kernel(&mut cache, 3, sigma), gaussian_kernel_generator_sigma(3, sigma));
+            assert!(cache.len() <= GAUSSIAN_KERNEL_CACHE_SIZE);
+        }
+    }
+}
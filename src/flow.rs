@@ -0,0 +1,63 @@
+//! Optical flow estimation.
+//!
+use ndarray::prelude::*;
+use ndarray::Data;
+use super::filter::{mean_smooth, spatial_temporal_gradients, BorderType};
+
+/// Dense optical flow with the Lucas-Kanade method.
+///
+/// For each pixel, the 2x2 least-squares system built from the spatial and
+/// temporal gradients over a `window` x `window` neighborhood is solved. Return
+/// `(u, v)`, where `u` is the displacement along the second axis and `v` is the
+/// displacement along the first axis, i.e., `frame_b[[i + v, j + u]]` is
+/// approximately `frame_a[[i, j]]`. Pixels whose system is singular (e.g. in
+/// flat regions) get zero flow.
+///
+/// Lucas-Kanade assumes small displacements (about one pixel or less).
+/// * `window`: size of the neighborhood.
+/// * `border`: border type.
+///
+/// # Example
+/// ```
+/// use simplecv::filter::BorderType;
+/// use simplecv::flow::lucas_kanade_flow;
+/// let pattern = |i: f64, j: f64| 0.5 + 0.25 * (0.4 * j).sin() + 0.25 * (0.3 * i).cos();
+/// let frame_a = ndarray::Array::from_shape_fn((24, 24), |(i, j)| pattern(i as f64, j as f64));
+/// // move the pattern by 0.5 pixel along the second axis
+/// let frame_b = ndarray::Array::from_shape_fn((24, 24), |(i, j)| pattern(i as f64, j as f64 - 0.5));
+/// let (u, v) = lucas_kanade_flow(&frame_a, &frame_b, 7, BorderType::Replicate);
+/// for i in 8..16 {
+///     for j in 8..16 {
+///         assert!((u[[i, j]] - 0.5).abs() < 0.1);
+///         assert!(v[[i, j]].abs() < 0.1);
+///     }
+/// }
+/// ```
+pub fn lucas_kanade_flow<S, T>(frame_a: &ArrayBase<S, Ix2>, frame_b: &ArrayBase<T, Ix2>, window: usize, border: BorderType)
+    -> (Array<f64, Ix2>, Array<f64, Ix2>)
+    where S: Data<Elem=f64>, T: Data<Elem=f64>
+{
+    let (ix, iy, it) = spatial_temporal_gradients(frame_a, frame_b, border);
+    // window averages, which give the same solution as window sums
+    let sxx = mean_smooth(&(&ix * &ix), window, border);
+    let sxy = mean_smooth(&(&ix * &iy), window, border);
+    let syy = mean_smooth(&(&iy * &iy), window, border);
+    let sxt = mean_smooth(&(&ix * &it), window, border);
+    let syt = mean_smooth(&(&iy * &it), window, border);
+    let height = frame_a.shape()[0];
+    let width = frame_a.shape()[1];
+    let eps = 1e-9;
+    let mut u = Array::zeros((height, width));
+    let mut v = Array::zeros((height, width));
+    for i in 0..height {
+        for j in 0..width {
+            let det = sxx[[i, j]] * syy[[i, j]] - sxy[[i, j]] * sxy[[i, j]];
+            if det.abs() < eps {
+                continue;
+            }
+            u[[i, j]] = (-syy[[i, j]] * sxt[[i, j]] + sxy[[i, j]] * syt[[i, j]]) / det;
+            v[[i, j]] = (sxy[[i, j]] * sxt[[i, j]] - sxx[[i, j]] * syt[[i, j]]) / det;
+        }
+    }
+    (u, v)
+}
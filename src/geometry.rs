@@ -0,0 +1,83 @@
+//! Lightweight geometric primitives for image regions.
+//!
+//! Like the rest of the crate, `x` is the index of the first axis (row) and `y`
+//! is the index of the second axis (column).
+
+/// A pixel location.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Point {
+    pub x: usize,
+    pub y: usize,
+}
+
+impl Point {
+    pub fn new(x: usize, y: usize) -> Point {
+        Point { x, y }
+    }
+}
+
+/// An axis-aligned rectangle of pixels.
+///
+/// `(x, y)` is the top-left corner, `h` is the extent along the first axis and
+/// `w` is the extent along the second axis, so the rectangle covers the rows
+/// `x..x + h` and the columns `y..y + w`.
+/// # Example:
+/// ```
+/// use simplecv::geometry::{Point, Rect};
+/// let a = Rect::new(2, 3, 4, 5);
+/// assert_eq!(a.area(), 20);
+/// assert!(a.contains(Point::new(2, 3)));
+/// assert!(a.contains(Point::new(5, 7)));
+/// assert!(!a.contains(Point::new(6, 7)));
+/// assert!(!a.contains(Point::new(5, 8)));
+/// assert!(!a.contains(Point::new(1, 4)));
+///
+/// let b = Rect::new(4, 6, 10, 10);
+/// assert_eq!(a.intersection(&b), Some(Rect::new(4, 6, 2, 2)));
+/// assert_eq!(b.intersection(&a), Some(Rect::new(4, 6, 2, 2)));
+/// assert_eq!(a.intersection(&Rect::new(0, 0, 10, 10)), Some(a));
+/// // rectangles sharing only an edge do not intersect
+/// assert_eq!(a.intersection(&Rect::new(6, 3, 4, 5)), None);
+/// assert_eq!(a.intersection(&Rect::new(0, 0, 1, 1)), None);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub h: usize,
+    pub w: usize,
+}
+
+impl Rect {
+    pub fn new(x: usize, y: usize, h: usize, w: usize) -> Rect {
+        Rect { x, y, h, w }
+    }
+
+    /// The number of pixels covered by the rectangle.
+    pub fn area(&self) -> usize {
+        self.h * self.w
+    }
+
+    /// Whether the rectangle covers no pixel.
+    pub fn is_empty(&self) -> bool {
+        self.h == 0 || self.w == 0
+    }
+
+    /// Whether `p` lies inside the rectangle.
+    pub fn contains(&self, p: Point) -> bool {
+        p.x >= self.x && p.x < self.x + self.h && p.y >= self.y && p.y < self.y + self.w
+    }
+
+    /// The overlapping part of two rectangles, or `None` if they do not overlap.
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        let x0 = usize::max(self.x, other.x);
+        let y0 = usize::max(self.y, other.y);
+        let x1 = usize::min(self.x + self.h, other.x + other.h);
+        let y1 = usize::min(self.y + self.w, other.y + other.w);
+        if x0 < x1 && y0 < y1 {
+            Some(Rect::new(x0, y0, x1 - x0, y1 - y0))
+        } else {
+            None
+        }
+    }
+}
@@ -4,27 +4,43 @@
 //! crate to read images and store data in `ndarray::Array`.
 use image::*;
 use ndarray::prelude::*;
-use super::utils::f2u;
+use std::fs;
+use std::path::Path;
+use ndarray::Data;
+use super::utils::{f2u, normalize_range};
 
-/// Read an image file into an array.
-/// 
-/// The return value is a 3D array of f64, in which all values are between 0 to 1.
-pub fn imread(filename: &str) -> ndarray::Array<f64, Ix3> {
-    let img = image::open(filename).expect("Read image failed!");
+/// Convert a decoded image into an array with values in \[0.0, 1.0\].
+fn image_to_array(img: &DynamicImage) -> ndarray::Array<f64, Ix3> {
     let (img_height, img_width) = img.dimensions();
     let mut buffer = Array::zeros((img_height as usize, img_width as usize, 3));
     for u in img.pixels() {
         let (x, y, color) = u;
         for c in 0..3 {            
-            buffer[[x as usize, y as usize, c as usize]] = color.data[c] as f64 / 255.0
+            buffer[[x as usize, y as usize, c]] = color.data[c] as f64 / 255.0
         }
     }
     buffer
 }
-/// Save an RGB image to an file.
+
+/// Read an image file into an array.
+/// 
+/// The return value is a 3D array of f64, in which all values are between 0 to 1.
+pub fn imread(filename: &str) -> ndarray::Array<f64, Ix3> {
+    let img = image::open(filename).expect("Read image failed!");
+    image_to_array(&img)
+}
+
+/// Decode an image from an in-memory buffer into an array.
 ///
-/// The argument must be a 3D array, in which all values must be in \[0.0, 1.0\].
-pub fn imsave(img: &Array<f64, Ix3>, filename: &str) {
+/// The format is guessed from the content. The return value is a 3D array
+/// of f64, in which all values are between 0 to 1.
+pub fn imread_from_bytes(bytes: &[u8]) -> ndarray::Array<f64, Ix3> {
+    let img = image::load_from_memory(bytes).expect("Decode image failed!");
+    image_to_array(&img)
+}
+
+/// Convert an RGB array with values in \[0.0, 1.0\] into an image buffer.
+fn array_to_image(img: &Array<f64, Ix3>) -> RgbImage {
     let shape = img.shape();
     let height = shape[0] as u32;
     let width = shape[1] as u32;
@@ -34,13 +50,84 @@ pub fn imsave(img: &Array<f64, Ix3>, filename: &str) {
         assert_eq!(val.len(), 3);
         *pixel = image::Rgb([f2u(val[0]), f2u(val[1]), f2u(val[2])]);
     }
-    buffer.save(filename).expect("Error in saving image!");
+    buffer
+}
+
+/// Save an RGB image to an file.
+///
+/// The argument must be a 3D array, in which all values must be in \[0.0, 1.0\].
+pub fn imsave(img: &Array<f64, Ix3>, filename: &str) {
+    array_to_image(img).save(filename).expect("Error in saving image!");
+}
+
+/// Save an RGB image to an file in the given format.
+///
+/// Unlike `imsave`, the format does not depend on the extension of `filename`.
+/// The argument must be a 3D array, in which all values must be in \[0.0, 1.0\].
+/// # Example:
+/// ```
+/// use simplecv::io::*;
+/// let filename = std::env::temp_dir().join("simplecv_imsave_format.dat");
+/// let filename = filename.to_str().unwrap();
+/// imsave_format(&ndarray::Array::from_elem((4, 3, 3), 0.6), filename, image::ImageFormat::PNG);
+/// let bytes = std::fs::read(filename).unwrap();
+/// assert_eq!(&bytes[1..4], b"PNG");
+/// assert_eq!(image::guess_format(&bytes).unwrap(), image::ImageFormat::PNG);
+/// ```
+pub fn imsave_format(img: &Array<f64, Ix3>, filename: &str, format: ImageFormat) {
+    let mut file = fs::File::create(filename).expect("Error in saving image!");
+    DynamicImage::ImageRgb8(array_to_image(img)).write_to(&mut file, format)
+                                                 .expect("Error in saving image!");
+}
+
+/// Encode an RGB image into an in-memory buffer in the given format.
+///
+/// The argument must be a 3D array, in which all values must be in \[0.0, 1.0\].
+/// # Example:
+/// ```
+/// use simplecv::io::*;
+/// let img = ndarray::Array::from_shape_fn((5, 4, 3), |(i, j, c)| (i * 12 + j * 3 + c) as f64 / 60.0);
+/// let bytes = imencode(&img, image::ImageFormat::PNG);
+/// let decoded = imread_from_bytes(&bytes);
+/// assert!(simplecv::utils::max_diff(&decoded, &img) <= 0.5 / 255.0 + 1e-9);
+/// ```
+pub fn imencode(img: &Array<f64, Ix3>, format: ImageFormat) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    DynamicImage::ImageRgb8(array_to_image(img)).write_to(&mut bytes, format)
+                                                 .expect("Error in encoding image!");
+    bytes
 }
 
 /// Save an grayscale image to an file.
 ///
-/// The argument must be a 2D array, in which all values must be in \[0.0, 1.0\].
-pub fn imsave_gray(img: &Array<f64, Ix2>, filename: &str) {
+/// The argument must be a 2D array. If `auto_normalize` is true, the values
+/// are linearly rescaled to \[0.0, 1.0\] by
+/// [normalize_range()](../utils/fn.normalize_range.html) before saving, so
+/// outputs of filters like `sobel()` or `laplacian()` can be saved directly.
+/// Otherwise all values must be in \[0.0, 1.0\].
+/// # Example:
+/// ```
+/// use simplecv::filter::{sobel, BorderType};
+/// use simplecv::io::*;
+/// // a bright bar gives both positive and negative gradients
+/// let img = ndarray::Array::from_shape_fn((8, 12), |(_, j)| if (4..8).contains(&j) { 1.0 } else { 0.0 });
+/// let gradient = sobel(&img, 3, 1, 0, BorderType::Replicate);
+/// let filename = std::env::temp_dir().join("simplecv_imsave_gray_normalized.png");
+/// let filename = filename.to_str().unwrap();
+/// imsave_gray(&gradient, filename, true);
+/// let saved = imread(filename);
+/// assert_eq!(simplecv::utils::f2u(saved[[4, 0, 0]]), 128);
+/// assert_eq!(simplecv::utils::f2u(saved[[4, 4, 0]]), 255);
+/// assert_eq!(simplecv::utils::f2u(saved[[4, 8, 0]]), 0);
+/// ```
+pub fn imsave_gray<S>(img: &ArrayBase<S, Ix2>, filename: &str, auto_normalize: bool)
+    where S: Data<Elem=f64>
+{
+    let img = if auto_normalize {
+        normalize_range(img, 0.0, 1.0)
+    } else {
+        img.to_owned()
+    };
     let shape = img.shape();
     let height = shape[0] as u32;
     let width = shape[1] as u32;
@@ -51,3 +138,90 @@ pub fn imsave_gray(img: &Array<f64, Ix2>, filename: &str) {
     }
     buffer.save(filename).expect("Error in saving image!");
 }
+
+/// Images which can be saved to a file.
+///
+/// It is implemented for 2D (grayscale) and 3D (RGB) arrays, and is used by
+/// [imsave_auto()](./fn.imsave_auto.html).
+pub trait SaveImage {
+    /// Save the image to a file.
+    fn save_image(&self, filename: &str);
+}
+
+impl SaveImage for Array<f64, Ix2> {
+    fn save_image(&self, filename: &str) {
+        imsave_gray(self, filename, false);
+    }
+}
+
+impl SaveImage for Array<f64, Ix3> {
+    fn save_image(&self, filename: &str) {
+        imsave(self, filename);
+    }
+}
+
+/// Save a grayscale or an RGB image to an file.
+///
+/// 2D arrays are saved by `imsave_gray` and 3D arrays are saved by `imsave`.
+/// All values must be in \[0.0, 1.0\].
+/// # Example:
+/// ```
+/// use simplecv::io::*;
+/// let dir = std::env::temp_dir();
+/// let gray_file = dir.join("simplecv_imsave_auto_gray.png");
+/// let gray_file = gray_file.to_str().unwrap();
+/// imsave_auto(&ndarray::Array::from_elem((4, 3), 0.2), gray_file);
+/// assert_eq!(imread(gray_file).shape(), &[4, 3, 3]);
+/// let rgb_file = dir.join("simplecv_imsave_auto_rgb.png");
+/// let rgb_file = rgb_file.to_str().unwrap();
+/// imsave_auto(&ndarray::Array::from_elem((4, 3, 3), 0.6), rgb_file);
+/// assert_eq!(simplecv::utils::f2u(imread(rgb_file)[[1, 2, 0]]), simplecv::utils::f2u(0.6));
+/// ```
+pub fn imsave_auto<D>(img: &Array<f64, D>, filename: &str)
+    where D: Dimension, Array<f64, D>: SaveImage
+{
+    img.save_image(filename);
+}
+
+/// Apply `op` to every image in `input_dir` and save the results to `output_dir`.
+///
+/// The results keep the file names of the inputs, and `output_dir` is created
+/// if it does not exist. Files which cannot be decoded as images are skipped.
+/// Return the number of processed images.
+/// # Example:
+/// ```
+/// use simplecv::io::*;
+/// let dir = std::env::temp_dir().join("simplecv_process_directory");
+/// let input_dir = dir.join("input");
+/// let output_dir = dir.join("output");
+/// std::fs::create_dir_all(&input_dir).unwrap();
+/// imsave(&ndarray::Array::from_elem((4, 3, 3), 0.2), input_dir.join("a.png").to_str().unwrap());
+/// imsave(&ndarray::Array::from_elem((2, 5, 3), 0.8), input_dir.join("b.png").to_str().unwrap());
+/// std::fs::write(input_dir.join("notes.txt"), "not an image").unwrap();
+/// let count = process_directory(input_dir.to_str().unwrap(), output_dir.to_str().unwrap(), |img| img);
+/// assert_eq!(count, 2);
+/// assert_eq!(imread(output_dir.join("a.png").to_str().unwrap()).shape(), &[4, 3, 3]);
+/// assert_eq!(imread(output_dir.join("b.png").to_str().unwrap()).shape(), &[2, 5, 3]);
+/// assert!(!output_dir.join("notes.txt").exists());
+/// ```
+pub fn process_directory<F>(input_dir: &str, output_dir: &str, op: F) -> usize
+    where F: Fn(Array<f64, Ix3>) -> Array<f64, Ix3>
+{
+    fs::create_dir_all(output_dir).expect("Create output directory failed!");
+    let mut count = 0;
+    for entry in fs::read_dir(input_dir).expect("Read input directory failed!") {
+        let path = entry.expect("Read input directory failed!").path();
+        if !path.is_file() {
+            continue;
+        }
+        let img = match image::open(&path) {
+            Ok(img) => img,
+            Err(_) => continue,
+        };
+        let result = op(image_to_array(&img));
+        let out_path = Path::new(output_dir).join(path.file_name().unwrap());
+        imsave(&result, out_path.to_str().expect("Invalid output path!"));
+        count += 1;
+    }
+    count
+}
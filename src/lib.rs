@@ -6,6 +6,21 @@
 extern crate ndarray;
 
 pub mod utils;
+pub mod geometry;
 pub mod io;
 pub mod color;
+pub mod arithmetic;
 pub mod filter;
+pub mod morph;
+pub mod transform;
+pub mod flow;
+pub mod noise;
+pub mod contour;
+pub mod compositing;
+pub mod draw;
+pub mod features;
+pub mod segmentation;
+pub mod stereo;
+pub mod registration;
+#[cfg(feature = "rustfft")]
+pub mod fft;
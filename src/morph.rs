@@ -0,0 +1,243 @@
+//! Mathematical morphology.
+//!
+use ndarray::prelude::*;
+use ndarray::{Data, DataMut};
+use super::filter::{access_img_border, BorderType};
+
+/// Apply `reduce` over the `ksize` x `ksize` neighborhood of each pixel.
+fn rank_filter_<S, T, F>(src: &ArrayBase<S, Ix2>, ksize: usize, border: BorderType, init: f64, reduce: F, out: &mut ArrayBase<T, Ix2>)
+    where S: Data<Elem=f64>, T: DataMut<Elem=f64>, F: Fn(f64, f64) -> f64
+{
+    assert!(ksize % 2 == 1, "ksize should be odd.");
+    assert_eq!(src.shape(), out.shape());
+    let half = (ksize / 2) as i32;
+    for ((x, y), v) in out.indexed_iter_mut() {
+        let mut acc = init;
+        for dx in -half..=half {
+            for dy in -half..=half {
+                acc = reduce(acc, access_img_border(src, x as i32 + dx, y as i32 + dy, border));
+            }
+        }
+        *v = acc;
+    }
+}
+
+/// Erode the image with a `ksize` x `ksize` square structuring element.
+///
+/// The output buffer should be allocated by users.
+/// * `ksize`: the size of the structuring element, should be odd.
+/// * `border`: how to deal with the border.
+pub fn erode_<S, T>(src: &ArrayBase<S, Ix2>, ksize: usize, border: BorderType, out: &mut ArrayBase<T, Ix2>)
+    where S: Data<Elem=f64>, T: DataMut<Elem=f64>
+{
+    rank_filter_(src, ksize, border, f64::INFINITY, f64::min, out);
+}
+
+/// Erode the image with a `ksize` x `ksize` square structuring element.
+///
+/// Each pixel is replaced by the minimum of its neighborhood.
+/// * `ksize`: the size of the structuring element, should be odd.
+/// * `border`: how to deal with the border.
+///
+/// # Example
+/// ```
+/// use simplecv::filter::BorderType;
+/// let mut img = ndarray::Array::zeros((5, 5));
+/// img[[2, 2]] = 1.0;
+/// let eroded = simplecv::morph::erode(&img, 3, BorderType::Replicate);
+/// assert_eq!(eroded[[2, 2]], 0.0);
+/// ```
+pub fn erode<S>(src: &ArrayBase<S, Ix2>, ksize: usize, border: BorderType) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    let mut buffer = Array::zeros((src.shape()[0], src.shape()[1]));
+    erode_(src, ksize, border, &mut buffer);
+    buffer
+}
+
+/// Dilate the image with a `ksize` x `ksize` square structuring element.
+///
+/// The output buffer should be allocated by users.
+/// * `ksize`: the size of the structuring element, should be odd.
+/// * `border`: how to deal with the border.
+pub fn dilate_<S, T>(src: &ArrayBase<S, Ix2>, ksize: usize, border: BorderType, out: &mut ArrayBase<T, Ix2>)
+    where S: Data<Elem=f64>, T: DataMut<Elem=f64>
+{
+    rank_filter_(src, ksize, border, f64::NEG_INFINITY, f64::max, out);
+}
+
+/// Dilate the image with a `ksize` x `ksize` square structuring element.
+///
+/// Each pixel is replaced by the maximum of its neighborhood.
+/// * `ksize`: the size of the structuring element, should be odd.
+/// * `border`: how to deal with the border.
+///
+/// # Example
+/// ```
+/// use simplecv::filter::BorderType;
+/// let mut img = ndarray::Array::zeros((5, 5));
+/// img[[2, 2]] = 1.0;
+/// let dilated = simplecv::morph::dilate(&img, 3, BorderType::Replicate);
+/// assert_eq!(dilated.sum(), 9.0);
+/// ```
+pub fn dilate<S>(src: &ArrayBase<S, Ix2>, ksize: usize, border: BorderType) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    let mut buffer = Array::zeros((src.shape()[0], src.shape()[1]));
+    dilate_(src, ksize, border, &mut buffer);
+    buffer
+}
+
+/// Reduce the shapes in a binary image to 1-pixel-wide skeletons.
+///
+/// Pixels with values > 0 are foreground. The Zhang-Suen thinning is
+/// applied until no pixel changes. Return an image of 0.0 and 1.0.
+///
+/// # Example
+/// ```
+/// let mut img = ndarray::Array::zeros((9, 20));
+/// for i in 2..7 {
+///     for j in 2..18 {
+///         img[[i, j]] = 1.0;
+///     }
+/// }
+/// let skeleton = simplecv::morph::skeletonize(&img);
+/// for j in 5..14 {
+///     assert_eq!(skeleton.column(j).sum(), 1.0);
+///     assert_eq!(skeleton[[4, j]], 1.0);
+/// }
+/// ```
+pub fn skeletonize<S>(binary: &ArrayBase<S, Ix2>) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    let height = binary.shape()[0];
+    let width = binary.shape()[1];
+    let mut img = binary.mapv(|v| v > 0.0);
+    let get = |img: &Array<bool, Ix2>, x: i32, y: i32| {
+        x >= 0 && y >= 0 && x < height as i32 && y < width as i32 && img[[x as usize, y as usize]]
+    };
+    let mut to_remove = Vec::new();
+    loop {
+        let mut changed = false;
+        for step in 0..2 {
+            for x in 0..height {
+                for y in 0..width {
+                    if !img[[x, y]] {
+                        continue;
+                    }
+                    let (xi, yi) = (x as i32, y as i32);
+                    // P2..P9, clockwise from the north neighbor
+                    let p = [
+                        get(&img, xi - 1, yi), get(&img, xi - 1, yi + 1),
+                        get(&img, xi, yi + 1), get(&img, xi + 1, yi + 1),
+                        get(&img, xi + 1, yi), get(&img, xi + 1, yi - 1),
+                        get(&img, xi, yi - 1), get(&img, xi - 1, yi - 1),
+                    ];
+                    let count = p.iter().filter(|&&v| v).count();
+                    let transitions = (0..8).filter(|&i| !p[i] && p[(i + 1) % 8]).count();
+                    let (a, b) = if step == 0 {
+                        (p[0] && p[2] && p[4], p[2] && p[4] && p[6])
+                    } else {
+                        (p[0] && p[2] && p[6], p[0] && p[4] && p[6])
+                    };
+                    if (2..=6).contains(&count) && transitions == 1 && !a && !b {
+                        to_remove.push((x, y));
+                    }
+                }
+            }
+            changed |= !to_remove.is_empty();
+            for (x, y) in to_remove.drain(..) {
+                img[[x, y]] = false;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    img.mapv(|v| if v { 1.0 } else { 0.0 })
+}
+
+/// Morphological opening: erosion followed by dilation.
+///
+/// It removes bright features smaller than the structuring element.
+/// * `ksize`: the size of the structuring element, should be odd.
+/// * `border`: how to deal with the border.
+pub fn open<S>(src: &ArrayBase<S, Ix2>, ksize: usize, border: BorderType) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    dilate(&erode(src, ksize, border), ksize, border)
+}
+
+/// Morphological closing: dilation followed by erosion.
+///
+/// It removes dark features smaller than the structuring element.
+/// * `ksize`: the size of the structuring element, should be odd.
+/// * `border`: how to deal with the border.
+pub fn close<S>(src: &ArrayBase<S, Ix2>, ksize: usize, border: BorderType) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    erode(&dilate(src, ksize, border), ksize, border)
+}
+
+/// Morphological gradient: the dilation minus the erosion.
+///
+/// It highlights the edges of objects.
+/// * `ksize`: the size of the structuring element, should be odd.
+/// * `border`: how to deal with the border.
+///
+/// # Example
+/// ```
+/// use simplecv::filter::BorderType;
+/// let img = ndarray::Array::from_shape_fn((5, 6), |(_, j)| if j < 3 { 0.0 } else { 1.0 });
+/// let grad = simplecv::morph::morph_gradient(&img, 3, BorderType::Replicate);
+/// assert_eq!(grad.column(0).sum(), 0.0);
+/// assert_eq!(grad.column(2).sum(), 5.0);
+/// assert_eq!(grad.column(3).sum(), 5.0);
+/// ```
+pub fn morph_gradient<S>(src: &ArrayBase<S, Ix2>, ksize: usize, border: BorderType) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    dilate(src, ksize, border) - erode(src, ksize, border)
+}
+
+/// Top-hat transform: the source minus its opening.
+///
+/// It extracts bright features smaller than the structuring element.
+/// * `ksize`: the size of the structuring element, should be odd.
+/// * `border`: how to deal with the border.
+///
+/// # Example
+/// ```
+/// use simplecv::filter::BorderType;
+/// let mut img = ndarray::Array::from_elem((7, 7), 0.8);
+/// img[[3, 3]] = 1.0;
+/// let result = simplecv::morph::top_hat(&img, 3, BorderType::Replicate);
+/// assert!((result[[3, 3]] - 0.2).abs() < 1e-9);
+/// assert!((result.sum() - 0.2).abs() < 1e-9);
+/// ```
+pub fn top_hat<S>(src: &ArrayBase<S, Ix2>, ksize: usize, border: BorderType) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    src - &open(src, ksize, border)
+}
+
+/// Black-hat transform: the closing minus the source.
+///
+/// It extracts dark features smaller than the structuring element.
+/// * `ksize`: the size of the structuring element, should be odd.
+/// * `border`: how to deal with the border.
+///
+/// # Example
+/// ```
+/// use simplecv::filter::BorderType;
+/// let mut img = ndarray::Array::from_elem((7, 7), 0.8);
+/// img[[3, 3]] = 0.2;
+/// let result = simplecv::morph::black_hat(&img, 3, BorderType::Replicate);
+/// assert!((result[[3, 3]] - 0.6).abs() < 1e-9);
+/// assert!((result.sum() - 0.6).abs() < 1e-9);
+/// ```
+pub fn black_hat<S>(src: &ArrayBase<S, Ix2>, ksize: usize, border: BorderType) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    close(src, ksize, border) - src
+}
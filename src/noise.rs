@@ -0,0 +1,85 @@
+//! Noise generators for degrading images.
+//!
+//! All generators take a `seed`, so the same seed always gives the same output.
+//! A small built-in pseudo-random generator is used to avoid extra dependencies.
+use ndarray::prelude::*;
+use ndarray::{Data, Dimension};
+
+/// The SplitMix64 pseudo-random generator.
+struct SplitMix64 {
+    state: u64
+}
+
+impl SplitMix64 {
+    fn new(seed: u64) -> SplitMix64 {
+        SplitMix64 { state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A uniform sample in [0, 1).
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// A standard normal sample by the Box-Muller transform.
+    fn next_gaussian(&mut self) -> f64 {
+        let u1 = 1.0 - self.next_f64();
+        let u2 = self.next_f64();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+}
+
+/// Add Gaussian noise with standard deviation `sigma` to an image.
+///
+/// The results are clipped to \[0.0, 1.0\].
+/// # Example:
+/// ```
+/// use simplecv::noise::add_gaussian_noise;
+/// let img = ndarray::Array::from_elem((64, 64), 0.5);
+/// let noisy = add_gaussian_noise(&img, 0.05, 42);
+/// assert_eq!(noisy, add_gaussian_noise(&img, 0.05, 42));
+/// let n = noisy.len() as f64;
+/// let std = ((&noisy - &img).mapv(|v| v * v).sum() / n).sqrt();
+/// assert!((std - 0.05).abs() < 0.005);
+/// ```
+pub fn add_gaussian_noise<S, D>(img: &ArrayBase<S, D>, sigma: f64, seed: u64) -> Array<f64, D>
+    where S: Data<Elem=f64>, D: Dimension
+{
+    let mut rng = SplitMix64::new(seed);
+    img.mapv(|v| (v + sigma * rng.next_gaussian()).clamp(0.0, 1.0))
+}
+
+/// Add salt-and-pepper noise to an image.
+///
+/// Each pixel is replaced with probability `prob`, by 0.0 (pepper) or 1.0 (salt)
+/// with equal chance.
+/// # Example:
+/// ```
+/// use simplecv::noise::add_salt_pepper;
+/// let img = ndarray::Array::from_elem((64, 64), 0.5);
+/// let noisy = add_salt_pepper(&img, 0.1, 7);
+/// assert_eq!(noisy, add_salt_pepper(&img, 0.1, 7));
+/// let changed = noisy.iter().filter(|v| **v != 0.5).count() as f64 / noisy.len() as f64;
+/// assert!((changed - 0.1).abs() < 0.02);
+/// assert!(noisy.iter().all(|v| *v == 0.0 || *v == 0.5 || *v == 1.0));
+/// ```
+pub fn add_salt_pepper<S, D>(img: &ArrayBase<S, D>, prob: f64, seed: u64) -> Array<f64, D>
+    where S: Data<Elem=f64>, D: Dimension
+{
+    assert!((0.0..=1.0).contains(&prob), "prob must be in [0, 1] in add_salt_pepper().");
+    let mut rng = SplitMix64::new(seed);
+    img.mapv(|v| {
+        if rng.next_f64() < prob {
+            if rng.next_f64() < 0.5 { 0.0 } else { 1.0 }
+        } else {
+            v
+        }
+    })
+}
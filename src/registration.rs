@@ -0,0 +1,200 @@
+//! Image registration, i.e., estimating the transform between two images.
+//!
+use ndarray::prelude::*;
+use ndarray::Data;
+use super::transform::{gaussian_pyramid, inverse_3x3};
+#[cfg(feature = "rustfft")]
+use super::fft::{fft2, ifft2, Complex};
+
+/// Estimate the translation between two images by phase correlation.
+///
+/// The normalized cross-power spectrum of the two images is transformed back to
+/// the spatial domain and the location of its peak is the shift. Return
+/// `(dx, dy)` along the first and the second axis, such that `b[[i, j]]` is
+/// approximately `a[[i - dx, j - dy]]`. Shifts are wrapped into
+/// `[-len / 2, len / 2)` of each axis, and the accuracy is one pixel.
+///
+/// This function requires the `rustfft` feature.
+///
+/// # Example
+/// ```
+/// use simplecv::registration::phase_correlation;
+/// let a = ndarray::Array::from_shape_fn((32, 32), |(i, j)| ((i * 7 + j * 13) % 17) as f64 / 17.0);
+/// let b = ndarray::Array::from_shape_fn((32, 32), |(i, j)| a[[(i + 32 - 3) % 32, (j + 32 - 5) % 32]]);
+/// let (dx, dy) = phase_correlation(&a, &b);
+/// assert!((dx - 3.0).abs() < 1.0);
+/// assert!((dy - 5.0).abs() < 1.0);
+/// ```
+#[cfg(feature = "rustfft")]
+pub fn phase_correlation<S, T>(a: &ArrayBase<S, Ix2>, b: &ArrayBase<T, Ix2>) -> (f64, f64)
+    where S: Data<Elem=f64>, T: Data<Elem=f64>
+{
+    assert_eq!(a.shape(), b.shape(), "Images must have the same shape in phase_correlation().");
+    let height = a.shape()[0];
+    let width = a.shape()[1];
+    let fa = fft2(a);
+    let fb = fft2(b);
+    let eps = 1e-12;
+    let mut cross = Array::zeros((height, width));
+    for i in 0..height {
+        for j in 0..width {
+            let r: Complex<f64> = fb[[i, j]] * fa[[i, j]].conj();
+            cross[[i, j]] = r / (r.norm() + eps);
+        }
+    }
+    let cross = ifft2(&cross);
+    let mut peak = (0, 0);
+    for i in 0..height {
+        for j in 0..width {
+            if cross[[i, j]].re > cross[[peak.0, peak.1]].re {
+                peak = (i, j);
+            }
+        }
+    }
+    let wrap = |p: usize, len: usize| if 2 * p >= len { p as f64 - len as f64 } else { p as f64 };
+    (wrap(peak.0, height), wrap(peak.1, width))
+}
+
+/// Estimate the affine transform from point correspondences.
+///
+/// Return the 2x3 matrix `m` minimizing the squared error of
+/// `m * (x, y, 1)^T - (x', y')^T` over all pairs of `(x, y)` in `src_pts` and
+/// `(x', y')` in `dst_pts`. At least 3 non-collinear correspondences are required.
+/// The result can be extended to a 3x3 matrix with the row `[0, 0, 1]` and used
+/// by [warp_perspective()](../transform/fn.warp_perspective.html).
+///
+/// # Example
+/// ```
+/// use simplecv::registration::estimate_affine;
+/// let m = ndarray::arr2(&[[0.9, -0.2, 3.0], [0.3, 1.1, -2.0]]);
+/// let src = vec![(0.0, 0.0), (10.0, 0.0), (0.0, 10.0), (7.0, 4.0), (3.0, 9.0)];
+/// let dst: Vec<(f64, f64)> = src.iter()
+///     .map(|&(x, y)| (m[[0, 0]] * x + m[[0, 1]] * y + m[[0, 2]], m[[1, 0]] * x + m[[1, 1]] * y + m[[1, 2]]))
+///     .collect();
+/// let estimated = estimate_affine(&src, &dst);
+/// assert!(simplecv::utils::max_diff(&estimated, &m) < 1e-9);
+/// ```
+pub fn estimate_affine(src_pts: &[(f64, f64)], dst_pts: &[(f64, f64)]) -> Array<f64, Ix2> {
+    assert_eq!(src_pts.len(), dst_pts.len(), "The numbers of points must be the same in estimate_affine().");
+    assert!(src_pts.len() >= 3, "At least 3 correspondences are required in estimate_affine().");
+    // normal equations: (A^T A) p = A^T b, where each row of A is (x, y, 1)
+    let mut ata: Array<f64, Ix2> = Array::zeros((3, 3));
+    let mut atb: Array<f64, Ix2> = Array::zeros((3, 2));
+    for (&(x, y), &(dx, dy)) in src_pts.iter().zip(dst_pts.iter()) {
+        let row = [x, y, 1.0];
+        for r in 0..3 {
+            for c in 0..3 {
+                ata[[r, c]] += row[r] * row[c];
+            }
+            atb[[r, 0]] += row[r] * dx;
+            atb[[r, 1]] += row[r] * dy;
+        }
+    }
+    inverse_3x3(&ata).dot(&atb).reversed_axes()
+}
+
+/// The search radius at each finer level of `match_template_pyramid()`.
+const PYRAMID_SEARCH_RADIUS: usize = 2;
+
+/// Subtract the mean from a template and return it with its sum of squares.
+fn zero_mean_template<S>(template: &ArrayBase<S, Ix2>) -> (Array<f64, Ix2>, f64)
+    where S: Data<Elem=f64>
+{
+    let mean = template.sum() / template.len() as f64;
+    let centered = template.mapv(|v| v - mean);
+    let sum_sq = centered.iter().map(|v| v * v).sum();
+    (centered, sum_sq)
+}
+
+/// Zero-mean normalized cross-correlation between a centered template and the
+/// window of `img` whose top-left corner is `(x, y)`.
+fn ncc_at<S>(img: &ArrayBase<S, Ix2>, centered: &Array<f64, Ix2>, sum_sq: f64, x: usize, y: usize) -> f64
+    where S: Data<Elem=f64>
+{
+    let window = img.slice(s![x..x + centered.shape()[0], y..y + centered.shape()[1]]);
+    let mean = window.sum() / window.len() as f64;
+    let mut cross = 0.0;
+    let mut var = 0.0;
+    for (a, t) in window.iter().zip(centered.iter()) {
+        cross += (a - mean) * t;
+        var += (a - mean) * (a - mean);
+    }
+    let denom = (var * sum_sq).sqrt();
+    if denom < 1e-12 { 0.0 } else { cross / denom }
+}
+
+/// Match a template against every location of an image.
+///
+/// The score is the zero-mean normalized cross-correlation in `[-1, 1]`, the
+/// same as OpenCV `matchTemplate()` with `TM_CCOEFF_NORMED`. The output has
+/// the shape `(height - t_height + 1, width - t_width + 1)`, and `[[x, y]]` is
+/// the score of the template placed with its top-left corner at `(x, y)`.
+/// Windows with no variation score 0.
+pub fn match_template<S, T>(img: &ArrayBase<S, Ix2>, template: &ArrayBase<T, Ix2>) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>, T: Data<Elem=f64>
+{
+    assert!(template.shape()[0] <= img.shape()[0] && template.shape()[1] <= img.shape()[1],
+            "The template must not be larger than the image in match_template().");
+    let (centered, sum_sq) = zero_mean_template(template);
+    let out_shape = (img.shape()[0] - template.shape()[0] + 1, img.shape()[1] - template.shape()[1] + 1);
+    Array::from_shape_fn(out_shape, |(x, y)| ncc_at(img, &centered, sum_sq, x, y))
+}
+
+/// Find the location of a template with a coarse-to-fine search on image pyramids.
+///
+/// The template is matched against every location at the coarsest level of
+/// the Gaussian pyramids with `levels` levels, then the best location is
+/// refined within a few pixels at each finer level. This is much faster than
+/// [match_template()](./fn.match_template.html) on large images, but the
+/// template must keep enough structure at the coarsest level to be found.
+/// Return the top-left corner `(x, y)` of the best match in the full resolution.
+/// # Example
+/// ```
+/// use simplecv::geometry::Rect;
+/// use simplecv::registration::{match_template, match_template_pyramid};
+/// let img = ndarray::Array::from_shape_fn((64, 64), |(i, j)| {
+///     (i as f64 * 0.2).sin() * (j as f64 * 0.15).cos() + ((i * i + 3 * j * j + i * j) % 11) as f64 / 22.0
+/// });
+/// let template = simplecv::transform::crop(&img, Rect::new(37, 21, 16, 16));
+/// let scores = match_template(&img, &template);
+/// let mut best = (0, 0);
+/// for ((x, y), &v) in scores.indexed_iter() {
+///     if v > scores[[best.0, best.1]] {
+///         best = (x, y);
+///     }
+/// }
+/// assert_eq!(best, (37, 21));
+/// assert_eq!(match_template_pyramid(&img, &template, 3), best);
+/// ```
+pub fn match_template_pyramid<S, T>(img: &ArrayBase<S, Ix2>, template: &ArrayBase<T, Ix2>, levels: usize) -> (usize, usize)
+    where S: Data<Elem=f64>, T: Data<Elem=f64>
+{
+    assert!(levels >= 1, "levels must be at least 1 in match_template_pyramid().");
+    let img_pyramid = gaussian_pyramid(img, levels);
+    let template_pyramid = gaussian_pyramid(template, levels);
+    let coarse = match_template(&img_pyramid[levels - 1], &template_pyramid[levels - 1]);
+    let mut best = (0, 0);
+    for ((x, y), &v) in coarse.indexed_iter() {
+        if v > coarse[[best.0, best.1]] {
+            best = (x, y);
+        }
+    }
+    for level in (0..levels - 1).rev() {
+        let image = &img_pyramid[level];
+        let (centered, sum_sq) = zero_mean_template(&template_pyramid[level]);
+        let max_x = image.shape()[0] - centered.shape()[0];
+        let max_y = image.shape()[1] - centered.shape()[1];
+        let (cx, cy) = (best.0 * 2, best.1 * 2);
+        let mut best_score = f64::NEG_INFINITY;
+        for x in cx.saturating_sub(PYRAMID_SEARCH_RADIUS)..=usize::min(cx + PYRAMID_SEARCH_RADIUS, max_x) {
+            for y in cy.saturating_sub(PYRAMID_SEARCH_RADIUS)..=usize::min(cy + PYRAMID_SEARCH_RADIUS, max_y) {
+                let score = ncc_at(image, &centered, sum_sq, x, y);
+                if score > best_score {
+                    best_score = score;
+                    best = (x, y);
+                }
+            }
+        }
+    }
+    best
+}
@@ -0,0 +1,208 @@
+//! Image segmentation.
+//!
+use ndarray::prelude::*;
+use ndarray::{Data, DataMut};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, VecDeque};
+use super::filter::Connectivity;
+
+/// Grow regions from seed points.
+///
+/// Starting from each seed, 4-connected neighbors whose values are within
+/// `tolerance` of the value of that seed are added to the region with a
+/// breadth-first search. Return the mask of all grown regions.
+///
+/// # Example
+/// ```
+/// use simplecv::segmentation::region_grow;
+/// let img = ndarray::Array::from_shape_fn((6, 6), |(_, j)| if j < 3 { 0.2 } else { 0.8 });
+/// let mask = region_grow(&img, &[(1, 1)], 0.1);
+/// assert_eq!(mask, img.mapv(|v| v < 0.5));
+/// ```
+pub fn region_grow<S>(img: &ArrayBase<S, Ix2>, seeds: &[(usize, usize)], tolerance: f64) -> Array<bool, Ix2>
+    where S: Data<Elem=f64>
+{
+    let height = img.shape()[0];
+    let width = img.shape()[1];
+    let mut mask = Array::from_elem((height, width), false);
+    let mut queue: VecDeque<(usize, usize)> = VecDeque::new();
+    for &(sx, sy) in seeds {
+        let seed_val = img[[sx, sy]];
+        // each seed grows with its own visited marks, as regions may overlap
+        let mut visited = Array::from_elem((height, width), false);
+        visited[[sx, sy]] = true;
+        queue.push_back((sx, sy));
+        while let Some((x, y)) = queue.pop_front() {
+            mask[[x, y]] = true;
+            for (dx, dy) in Connectivity::Four.offsets() {
+                let nx = x as i32 + dx;
+                let ny = y as i32 + dy;
+                if nx < 0 || ny < 0 || nx >= height as i32 || ny >= width as i32 {
+                    continue;
+                }
+                let (nx, ny) = (nx as usize, ny as usize);
+                if !visited[[nx, ny]] && (img[[nx, ny]] - seed_val).abs() <= tolerance {
+                    visited[[nx, ny]] = true;
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+    }
+    mask
+}
+
+/// Fill the connected region around `seed` with `new_val`, like a paint bucket.
+///
+/// Pixels connected to the seed through `connectivity` whose values are
+/// within `tolerance` of the seed value are replaced with `new_val`.
+///
+/// # Example
+/// ```
+/// use simplecv::filter::Connectivity;
+/// use simplecv::segmentation::flood_fill;
+/// let mut img = ndarray::Array::from_shape_fn((4, 6), |(_, j)| if j < 3 { 0.2 } else { 0.8 });
+/// flood_fill(&mut img, (0, 0), 1.0, 0.05, Connectivity::Four);
+/// assert!(img.column(2).iter().all(|&v| v == 1.0));
+/// assert!(img.column(3).iter().all(|&v| v == 0.8));
+/// ```
+pub fn flood_fill<B>(img: &mut ArrayBase<B, Ix2>, seed: (usize, usize), new_val: f64, tolerance: f64, connectivity: Connectivity)
+    where B: DataMut<Elem=f64>
+{
+    let height = img.shape()[0];
+    let width = img.shape()[1];
+    let seed_val = img[[seed.0, seed.1]];
+    let mut visited = Array::from_elem((height, width), false);
+    let mut queue: VecDeque<(usize, usize)> = VecDeque::new();
+    visited[[seed.0, seed.1]] = true;
+    queue.push_back(seed);
+    while let Some((x, y)) = queue.pop_front() {
+        img[[x, y]] = new_val;
+        for (dx, dy) in connectivity.offsets() {
+            let nx = x as i32 + dx;
+            let ny = y as i32 + dy;
+            if nx < 0 || ny < 0 || nx >= height as i32 || ny >= width as i32 {
+                continue;
+            }
+            let (nx, ny) = (nx as usize, ny as usize);
+            if !visited[[nx, ny]] && (img[[nx, ny]] - seed_val).abs() <= tolerance {
+                visited[[nx, ny]] = true;
+                queue.push_back((nx, ny));
+            }
+        }
+    }
+}
+
+/// Pixel waiting in the watershed flooding queue. Lower values and earlier
+/// insertions are popped first.
+struct FloodItem {
+    value: f64,
+    order: usize,
+    pos: (usize, usize),
+}
+
+impl PartialEq for FloodItem {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for FloodItem {}
+
+impl PartialOrd for FloodItem {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for FloodItem {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // reversed, since BinaryHeap is a max-heap
+        other.value.partial_cmp(&self.value).unwrap_or(Ordering::Equal)
+            .then_with(|| other.order.cmp(&self.order))
+    }
+}
+
+/// Marker-controlled watershed segmentation.
+///
+/// The `gradient` image (usually a gradient magnitude) is flooded from the
+/// seeds in `markers` in order of increasing value. In `markers`, 0 means
+/// unknown and every value > 0 is the label of a seed region. In the
+/// returned labels, each pixel takes the label of the region that reached
+/// it first, and pixels where two different regions meet are set to -1.
+///
+/// # Example
+/// ```
+/// use simplecv::segmentation::watershed;
+/// // a ridge along column 4 between two basins
+/// let gradient = ndarray::Array::from_shape_fn((5, 9), |(_, j)| 4.0 - (j as f64 - 4.0).abs());
+/// let mut markers = ndarray::Array::<i32, _>::zeros((5, 9));
+/// markers[[2, 0]] = 1;
+/// markers[[2, 8]] = 2;
+/// let labels = watershed(&gradient, &markers);
+/// assert!(labels.column(4).iter().all(|&v| v == -1));
+/// assert!(labels.column(1).iter().all(|&v| v == 1));
+/// assert!(labels.column(7).iter().all(|&v| v == 2));
+/// ```
+pub fn watershed<S, M>(gradient: &ArrayBase<S, Ix2>, markers: &ArrayBase<M, Ix2>) -> Array<i32, Ix2>
+    where S: Data<Elem=f64>, M: Data<Elem=i32>
+{
+    assert_eq!(gradient.shape(), markers.shape());
+    let height = gradient.shape()[0];
+    let width = gradient.shape()[1];
+    let mut labels = markers.mapv(|v| if v > 0 { v } else { 0 });
+    let mut queued = labels.mapv(|v| v > 0);
+    let mut heap = BinaryHeap::new();
+    let mut order = 0;
+    let neighbors = |x: usize, y: usize| {
+        Connectivity::Four.offsets().iter().filter_map(move |&(dx, dy)| {
+            let nx = x as i32 + dx;
+            let ny = y as i32 + dy;
+            if nx < 0 || ny < 0 || nx >= height as i32 || ny >= width as i32 {
+                None
+            } else {
+                Some((nx as usize, ny as usize))
+            }
+        })
+    };
+    for x in 0..height {
+        for y in 0..width {
+            if labels[[x, y]] <= 0 {
+                continue;
+            }
+            for (nx, ny) in neighbors(x, y) {
+                if !queued[[nx, ny]] {
+                    queued[[nx, ny]] = true;
+                    heap.push(FloodItem { value: gradient[[nx, ny]], order, pos: (nx, ny) });
+                    order += 1;
+                }
+            }
+        }
+    }
+    while let Some(item) = heap.pop() {
+        let (x, y) = item.pos;
+        let mut label = 0;
+        for (nx, ny) in neighbors(x, y) {
+            let l = labels[[nx, ny]];
+            if l > 0 {
+                if label == 0 {
+                    label = l;
+                } else if label != l {
+                    label = -1;
+                    break;
+                }
+            }
+        }
+        labels[[x, y]] = label;
+        if label <= 0 {
+            continue;
+        }
+        for (nx, ny) in neighbors(x, y) {
+            if !queued[[nx, ny]] {
+                queued[[nx, ny]] = true;
+                heap.push(FloodItem { value: gradient[[nx, ny]], order, pos: (nx, ny) });
+                order += 1;
+            }
+        }
+    }
+    labels
+}
@@ -0,0 +1,62 @@
+//! Stereo matching.
+//!
+use ndarray::prelude::*;
+use ndarray::Data;
+use super::features::census_transform;
+use super::filter::{mean_smooth, BorderType};
+
+/// Compute the disparity map of a rectified stereo pair.
+///
+/// Pixels are described by their 3x3 census transforms, and the matching cost
+/// of a disparity is the Hamming distance of the descriptors summed over a
+/// `window` x `window` block, where `window` must be odd. For each pixel, the
+/// disparity in `0..=max_disparity` with the lowest cost is selected.
+///
+/// The disparity is along the second axis: the pixel `(i, j)` of `left`
+/// matches the pixel `(i, j - d)` of `right`. Disparities which go out of the
+/// right image are not considered.
+/// # Example
+/// ```
+/// use simplecv::noise::add_gaussian_noise;
+/// let texture = add_gaussian_noise(&ndarray::Array::from_elem((20, 40), 0.5), 0.2, 7);
+/// use ndarray::s;
+/// let left = texture.slice(s![.., 0..32]).to_owned();
+/// let right = texture.slice(s![.., 3..35]).to_owned();
+/// let disparity = simplecv::stereo::stereo_match_sad(&left, &right, 6, 5);
+/// for i in 3..17 {
+///     for j in 8..30 {
+///         assert_eq!(disparity[[i, j]], 3.0);
+///     }
+/// }
+/// ```
+pub fn stereo_match_sad<S, T>(left: &ArrayBase<S, Ix2>, right: &ArrayBase<T, Ix2>, max_disparity: usize, window: usize)
+    -> Array<f64, Ix2>
+    where S: Data<Elem=f64>, T: Data<Elem=f64>
+{
+    assert_eq!(left.shape(), right.shape(), "Images must have the same shape in stereo_match_sad().");
+    let height = left.shape()[0];
+    let width = left.shape()[1];
+    let census_left = census_transform(left, 3, BorderType::Replicate);
+    let census_right = census_transform(right, 3, BorderType::Replicate);
+    // larger than any Hamming distance of 8-bit descriptors
+    let invalid_cost = 9.0;
+    let mut best_cost = Array::from_elem((height, width), f64::INFINITY);
+    let mut disparity = Array::zeros((height, width));
+    for d in 0..=max_disparity {
+        let hamming = Array::from_shape_fn((height, width), |(i, j)| {
+            if j < d {
+                invalid_cost
+            } else {
+                (census_left[[i, j]] ^ census_right[[i, j - d]]).count_ones() as f64
+            }
+        });
+        let cost = mean_smooth(&hamming, window, BorderType::Replicate);
+        for ((i, j), &c) in cost.indexed_iter() {
+            if j >= d && c < best_cost[[i, j]] {
+                best_cost[[i, j]] = c;
+                disparity[[i, j]] = d as f64;
+            }
+        }
+    }
+    disparity
+}
@@ -0,0 +1,485 @@
+//! Geometric transformations of images.
+//!
+use ndarray::prelude::*;
+use ndarray::Data;
+use super::filter::{access_img_border, gaussian_smooth_sigma, BorderType};
+use super::geometry::Rect;
+
+/// Concatenate two images horizontally, putting `b` on the right of `a`.
+///
+/// Both images must have the same height and the same number of channels.
+/// # Example:
+/// ```
+/// use ndarray::Array;
+/// let a = Array::zeros((2, 2, 3));
+/// let b = Array::ones((2, 3, 3));
+/// let c = simplecv::transform::hconcat(&a, &b);
+/// assert_eq!(c.shape(), &[2, 5, 3]);
+/// assert_eq!(c[[1, 1, 2]], 0.0);
+/// assert_eq!(c[[1, 2, 0]], 1.0);
+/// ```
+pub fn hconcat<A, B>(a: &ArrayBase<A, Ix3>, b: &ArrayBase<B, Ix3>) -> Array<f64, Ix3>
+    where A: Data<Elem=f64>, B: Data<Elem=f64>
+{
+    assert_eq!(a.shape()[0], b.shape()[0], "Heights of images must be the same in hconcat().");
+    assert_eq!(a.shape()[2], b.shape()[2], "Channels of images must be the same in hconcat().");
+    ndarray::stack(Axis(1), &[a.view(), b.view()]).unwrap()
+}
+
+/// Concatenate two images vertically, putting `b` below `a`.
+///
+/// Both images must have the same width and the same number of channels.
+/// # Example:
+/// ```
+/// use ndarray::Array;
+/// let a = Array::zeros((2, 2, 3));
+/// let b = Array::ones((1, 2, 3));
+/// let c = simplecv::transform::vconcat(&a, &b);
+/// assert_eq!(c.shape(), &[3, 2, 3]);
+/// assert_eq!(c[[1, 0, 0]], 0.0);
+/// assert_eq!(c[[2, 1, 1]], 1.0);
+/// ```
+pub fn vconcat<A, B>(a: &ArrayBase<A, Ix3>, b: &ArrayBase<B, Ix3>) -> Array<f64, Ix3>
+    where A: Data<Elem=f64>, B: Data<Elem=f64>
+{
+    assert_eq!(a.shape()[1], b.shape()[1], "Widths of images must be the same in vconcat().");
+    assert_eq!(a.shape()[2], b.shape()[2], "Channels of images must be the same in vconcat().");
+    ndarray::stack(Axis(0), &[a.view(), b.view()]).unwrap()
+}
+
+/// Copy the region covered by `rect` out of an image.
+///
+/// `rect` must lie inside the image.
+/// # Example:
+/// ```
+/// use simplecv::geometry::Rect;
+/// let img = ndarray::Array::from_shape_fn((4, 5), |(i, j)| (i * 5 + j) as f64);
+/// let c = simplecv::transform::crop(&img, Rect::new(1, 2, 2, 3));
+/// assert_eq!(c, ndarray::arr2(&[[7.0, 8.0, 9.0], [12.0, 13.0, 14.0]]));
+/// ```
+pub fn crop<S>(img: &ArrayBase<S, Ix2>, rect: Rect) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    assert!(rect.x + rect.h <= img.shape()[0] && rect.y + rect.w <= img.shape()[1],
+            "rect must lie inside the image in crop().");
+    roi(img, rect).to_owned()
+}
+
+/// Get a read-only view of the region covered by `rect` without copying.
+///
+/// Use it instead of [crop()](./fn.crop.html) when the region is only read.
+/// `rect` must lie inside the image.
+/// # Example:
+/// ```
+/// use simplecv::geometry::Rect;
+/// let img = ndarray::Array::from_shape_fn((4, 5), |(i, j)| (i * 5 + j) as f64);
+/// let view = simplecv::transform::roi(&img, Rect::new(1, 2, 2, 3));
+/// assert_eq!(view.shape(), &[2, 3]);
+/// assert_eq!(view[[0, 0]], img[[1, 2]]);
+/// assert_eq!(view[[1, 2]], img[[2, 4]]);
+/// assert_eq!(&view[[0, 0]] as *const f64, &img[[1, 2]] as *const f64);
+/// ```
+pub fn roi<'a, S>(img: &'a ArrayBase<S, Ix2>, rect: Rect) -> ArrayView2<'a, f64>
+    where S: Data<Elem=f64>
+{
+    assert!(rect.x + rect.h <= img.shape()[0] && rect.y + rect.w <= img.shape()[1],
+            "rect must lie inside the image in roi().");
+    img.slice(s![rect.x..rect.x + rect.h, rect.y..rect.y + rect.w])
+}
+
+/// Sample an image at a fractional location with bilinear interpolation.
+///
+/// The four integer neighbors of `(x, y)` are read with the same border
+/// handling as the filters, so pixels outside the image are handled by `border`.
+/// # Example:
+/// ```
+/// use simplecv::filter::BorderType;
+/// use simplecv::transform::sample_bilinear;
+/// let img = ndarray::arr2(&[[0.0, 1.0], [2.0, 3.0]]);
+/// // 0.0 * 0.75 * 0.5 + 1.0 * 0.75 * 0.5 + 2.0 * 0.25 * 0.5 + 3.0 * 0.25 * 0.5
+/// let v = sample_bilinear(&img, 0.25, 0.5, BorderType::Replicate);
+/// assert!((v - 1.0).abs() < 1e-12);
+/// assert_eq!(sample_bilinear(&img, 1.5, 0.0, BorderType::Constant(0.0)), 1.0);
+/// ```
+pub fn sample_bilinear<S>(img: &ArrayBase<S, Ix2>, x: f64, y: f64, border: BorderType) -> f64
+    where S: Data<Elem=f64>
+{
+    let x0 = x.floor();
+    let y0 = y.floor();
+    let fx = x - x0;
+    let fy = y - y0;
+    let x0 = x0 as i32;
+    let y0 = y0 as i32;
+    access_img_border(img, x0, y0, border) * (1.0 - fx) * (1.0 - fy)
+        + access_img_border(img, x0 + 1, y0, border) * fx * (1.0 - fy)
+        + access_img_border(img, x0, y0 + 1, border) * (1.0 - fx) * fy
+        + access_img_border(img, x0 + 1, y0 + 1, border) * fx * fy
+}
+
+/// Compute the inverse of a 3x3 matrix.
+pub(crate) fn inverse_3x3<S>(m: &ArrayBase<S, Ix2>) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    assert_eq!(m.shape(), &[3, 3]);
+    let cofactor = |r0: usize, r1: usize, c0: usize, c1: usize| m[[r0, c0]] * m[[r1, c1]] - m[[r0, c1]] * m[[r1, c0]];
+    let adjugate = arr2(&[[cofactor(1, 2, 1, 2), -cofactor(0, 2, 1, 2), cofactor(0, 1, 1, 2)],
+                          [-cofactor(1, 2, 0, 2), cofactor(0, 2, 0, 2), -cofactor(0, 1, 0, 2)],
+                          [cofactor(1, 2, 0, 1), -cofactor(0, 2, 0, 1), cofactor(0, 1, 0, 1)]]);
+    let det = m[[0, 0]] * adjugate[[0, 0]] + m[[0, 1]] * adjugate[[1, 0]] + m[[0, 2]] * adjugate[[2, 0]];
+    assert!(det.abs() > 1e-12, "The matrix is singular.");
+    adjugate / det
+}
+
+/// Warp an image with a perspective transform.
+///
+/// `h` is a 3x3 homography mapping a source location `(x, y, 1)` to the output
+/// location `(x', y', w')`, i.e., `(x' / w', y' / w')`, where `x` and `y` are
+/// the indices of the first and the second axis. The output image has the shape
+/// `out_shape`, and each output pixel is sampled from the source image by the
+/// inverse mapping with bilinear interpolation. Locations outside the source
+/// image are handled by `border`.
+///
+/// # Example:
+/// ```
+/// use simplecv::filter::BorderType;
+/// use simplecv::transform::warp_perspective;
+/// let mut img = ndarray::Array::zeros((11, 11));
+/// for &(x, y) in &[(0, 0), (0, 10), (10, 0), (10, 10)] {
+///     img[[x, y]] = 1.0;
+/// }
+/// let identity = ndarray::Array::eye(3);
+/// assert_eq!(warp_perspective(&img, &identity, (11, 11), BorderType::Constant(0.0)), img);
+/// // (x, y) -> (x, y) / (0.1 * x + 1)
+/// let h = ndarray::arr2(&[[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.1, 0.0, 1.0]]);
+/// let warped = warp_perspective(&img, &h, (11, 11), BorderType::Constant(0.0));
+/// for &(x, y) in &[(0, 0), (0, 10), (5, 0), (5, 5)] {
+///     assert!((warped[[x, y]] - 1.0).abs() < 1e-9);
+/// }
+/// assert_eq!(warped[[10, 10]], 0.0);
+/// ```
+pub fn warp_perspective<S, H>(img: &ArrayBase<S, Ix2>, h: &ArrayBase<H, Ix2>, out_shape: (usize, usize), border: BorderType) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>, H: Data<Elem=f64>
+{
+    let inv = inverse_3x3(h);
+    Array::from_shape_fn(out_shape, |(i, j)| {
+        let (x, y) = (i as f64, j as f64);
+        let sx = inv[[0, 0]] * x + inv[[0, 1]] * y + inv[[0, 2]];
+        let sy = inv[[1, 0]] * x + inv[[1, 1]] * y + inv[[1, 2]];
+        let sw = inv[[2, 0]] * x + inv[[2, 1]] * y + inv[[2, 2]];
+        if sw.abs() < 1e-12 {
+            match border {
+                BorderType::Constant(v) => v,
+                _ => 0.0
+            }
+        } else {
+            sample_bilinear(img, sx / sw, sy / sw, border)
+        }
+    })
+}
+
+/// Map an output coordinate to the source coordinate when resizing.
+///
+/// The centers of pixels are aligned, which is the same as OpenCV `resize()`.
+fn resize_source_coord(dst: usize, src_len: usize, dst_len: usize) -> f64 {
+    (dst as f64 + 0.5) * (src_len as f64 / dst_len as f64) - 0.5
+}
+
+/// Resize an image with nearest neighbor interpolation.
+fn resize_nearest<S>(img: &ArrayBase<S, Ix2>, new_h: usize, new_w: usize) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    let height = img.shape()[0];
+    let width = img.shape()[1];
+    Array::from_shape_fn((new_h, new_w), |(i, j)| {
+        let x = (resize_source_coord(i, height, new_h).round().max(0.0) as usize).min(height - 1);
+        let y = (resize_source_coord(j, width, new_w).round().max(0.0) as usize).min(width - 1);
+        img[[x, y]]
+    })
+}
+
+/// Resize an image with bilinear interpolation.
+///
+/// Sampling locations outside the image are clamped to the border.
+fn resize_bilinear<S>(img: &ArrayBase<S, Ix2>, new_h: usize, new_w: usize) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    let height = img.shape()[0];
+    let width = img.shape()[1];
+    Array::from_shape_fn((new_h, new_w), |(i, j)| {
+        let x = resize_source_coord(i, height, new_h).clamp(0.0, (height - 1) as f64);
+        let y = resize_source_coord(j, width, new_w).clamp(0.0, (width - 1) as f64);
+        sample_bilinear(img, x, y, BorderType::Replicate)
+    })
+}
+
+/// Compute the source pixels covered by each output pixel along one axis.
+///
+/// Return a list of `(source index, covered length)` for each output index.
+fn area_weights(src_len: usize, dst_len: usize) -> Vec<Vec<(usize, f64)>> {
+    let scale = src_len as f64 / dst_len as f64;
+    (0..dst_len).map(|i| {
+        let start = i as f64 * scale;
+        let end = (i + 1) as f64 * scale;
+        let first = start.floor() as usize;
+        let last = usize::min(end.ceil() as usize, src_len);
+        (first..last).map(|k| (k, f64::min(end, (k + 1) as f64) - f64::max(start, k as f64)))
+                     .filter(|&(_, w)| w > 0.0)
+                     .collect()
+    }).collect()
+}
+
+/// Compute the weights of source pixels for each output pixel along one axis.
+///
+/// A shrunk axis is averaged over the covered source pixels, while an
+/// enlarged axis is linearly interpolated. The weights of each output index
+/// sum to 1.
+fn area_axis_weights(src_len: usize, dst_len: usize) -> Vec<Vec<(usize, f64)>> {
+    if dst_len > src_len {
+        (0..dst_len).map(|i| {
+            let x = resize_source_coord(i, src_len, dst_len).clamp(0.0, (src_len - 1) as f64);
+            let x0 = x.floor() as usize;
+            let x1 = usize::min(x0 + 1, src_len - 1);
+            let t = x - x0 as f64;
+            vec![(x0, 1.0 - t), (x1, t)]
+        }).collect()
+    } else {
+        let scale = src_len as f64 / dst_len as f64;
+        area_weights(src_len, dst_len).into_iter()
+                                      .map(|w| w.into_iter().map(|(k, l)| (k, l / scale)).collect())
+                                      .collect()
+    }
+}
+
+/// Resize an image by averaging the source pixels covered by each output pixel.
+///
+/// Each axis is handled on its own, so an enlarged axis is linearly
+/// interpolated even if the other one is shrunk.
+fn resize_area<S>(img: &ArrayBase<S, Ix2>, new_h: usize, new_w: usize) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    let weights_x = area_axis_weights(img.shape()[0], new_h);
+    let weights_y = area_axis_weights(img.shape()[1], new_w);
+    Array::from_shape_fn((new_h, new_w), |(i, j)| {
+        let mut sum = 0.0;
+        for &(x, wx) in &weights_x[i] {
+            for &(y, wy) in &weights_y[j] {
+                sum += img[[x, y]] * wx * wy;
+            }
+        }
+        sum
+    })
+}
+
+/// The cubic convolution kernel with `a = -0.5`.
+fn cubic_weight(t: f64) -> f64 {
+    let a = -0.5;
+    let t = t.abs();
+    if t <= 1.0 {
+        (a + 2.0) * t.powi(3) - (a + 3.0) * t.powi(2) + 1.0
+    } else if t < 2.0 {
+        a * t.powi(3) - 5.0 * a * t.powi(2) + 8.0 * a * t - 4.0 * a
+    } else {
+        0.0
+    }
+}
+
+/// Resize an image with bicubic interpolation.
+///
+/// The standard cubic convolution kernel with `a = -0.5` is applied on the 4x4
+/// neighborhood of each sampling location, which gives smoother results than
+/// bilinear interpolation when upscaling. Sampling coordinates are clamped to
+/// the border.
+fn resize_bicubic<S>(img: &ArrayBase<S, Ix2>, new_h: usize, new_w: usize) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    let height = img.shape()[0];
+    let width = img.shape()[1];
+    Array::from_shape_fn((new_h, new_w), |(i, j)| {
+        let x = resize_source_coord(i, height, new_h);
+        let y = resize_source_coord(j, width, new_w);
+        let x0 = x.floor() as i32;
+        let y0 = y.floor() as i32;
+        let mut val = 0.0;
+        for dx in -1..3 {
+            let wx = cubic_weight(x - (x0 + dx) as f64);
+            let sx = (x0 + dx).clamp(0, height as i32 - 1) as usize;
+            for dy in -1..3 {
+                let wy = cubic_weight(y - (y0 + dy) as f64);
+                let sy = (y0 + dy).clamp(0, width as i32 - 1) as usize;
+                val += wx * wy * img[[sx, sy]];
+            }
+        }
+        val
+    })
+}
+
+/// Interpolation methods for resizing.
+#[derive(Copy, Clone, Debug)]
+pub enum Interpolation {
+    /// Take the value of the nearest pixel.
+    Nearest,
+    /// Linear interpolation on the 2x2 neighborhood.
+    Bilinear,
+    /// Cubic convolution on the 4x4 neighborhood.
+    Bicubic,
+    /// Average of the source pixels covered by each output pixel, which avoids
+    /// aliasing when downscaling. An enlarged axis is linearly interpolated
+    /// as in `Bilinear`.
+    Area,
+}
+
+/// Resize an image to `new_h` x `new_w` with the given interpolation method.
+///
+/// The centers of pixels are aligned, which is the same as OpenCV `resize()`.
+/// Sampling locations outside the image are clamped to the border.
+/// # Example:
+/// ```
+/// use simplecv::transform::{resize, Interpolation};
+/// let img = ndarray::arr2(&[[0.0, 1.0], [1.0, 0.0]]);
+/// let nearest = resize(&img, 4, 4, Interpolation::Nearest);
+/// assert_eq!(nearest, ndarray::arr2(&[[0.0, 0.0, 1.0, 1.0],
+///                                     [0.0, 0.0, 1.0, 1.0],
+///                                     [1.0, 1.0, 0.0, 0.0],
+///                                     [1.0, 1.0, 0.0, 0.0]]));
+/// let bilinear = resize(&img, 4, 4, Interpolation::Bilinear);
+/// assert_eq!(bilinear[[0, 0]], 0.0);
+/// assert_eq!(bilinear[[0, 3]], 1.0);
+/// assert_eq!(bilinear[[1, 1]], 0.375);
+///
+/// // bicubic interpolation is smoother than bilinear when upscaling
+/// let img = ndarray::Array::from_shape_fn((5, 5), |(i, j)| ((i * i + j * j) as f64 / 32.0));
+/// let second_diff_energy = |r: &ndarray::Array2<f64>| {
+///     let mut e = 0.0;
+///     for i in 0..r.shape()[0] {
+///         for j in 1..r.shape()[1] - 1 {
+///             e += (r[[i, j - 1]] - 2.0 * r[[i, j]] + r[[i, j + 1]]).powi(2);
+///         }
+///     }
+///     e
+/// };
+/// let bicubic = resize(&img, 20, 20, Interpolation::Bicubic);
+/// let bilinear = resize(&img, 20, 20, Interpolation::Bilinear);
+/// assert!(second_diff_energy(&bicubic) < second_diff_energy(&bilinear));
+///
+/// // area interpolation averages a fine checkerboard to gray instead of aliasing
+/// let checkerboard = ndarray::Array::from_shape_fn((16, 16), |(i, j)| ((i + j) % 2) as f64);
+/// let area = resize(&checkerboard, 4, 4, Interpolation::Area);
+/// assert!(area.iter().all(|&v| (v - 0.5).abs() < 1e-12));
+/// let area = resize(&checkerboard, 5, 5, Interpolation::Area);
+/// assert!(area.iter().all(|&v| (v - 0.5).abs() < 0.05));
+/// let nearest = resize(&checkerboard, 5, 5, Interpolation::Nearest);
+/// assert!(nearest.iter().all(|&v| v == 0.0 || v == 1.0));
+///
+/// // stripes along the shrunk axis are still averaged when the other axis is enlarged
+/// let stripes = ndarray::Array::from_shape_fn((100, 10), |(i, _)| (i % 2) as f64);
+/// let area = resize(&stripes, 10, 20, Interpolation::Area);
+/// assert!(area.iter().all(|&v| (v - 0.5).abs() < 1e-12));
+/// // enlarging both axes is the same as bilinear interpolation
+/// let img = ndarray::Array::from_shape_fn((5, 5), |(i, j)| ((i * i + j * j) as f64 / 32.0));
+/// let area = resize(&img, 9, 12, Interpolation::Area);
+/// let bilinear = resize(&img, 9, 12, Interpolation::Bilinear);
+/// assert!(simplecv::utils::max_diff(&area, &bilinear) < 1e-12);
+/// ```
+pub fn resize<S>(img: &ArrayBase<S, Ix2>, new_h: usize, new_w: usize, interp: Interpolation) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    match interp {
+        Interpolation::Nearest => resize_nearest(img, new_h, new_w),
+        Interpolation::Bilinear => resize_bilinear(img, new_h, new_w),
+        Interpolation::Bicubic => resize_bicubic(img, new_h, new_w),
+        Interpolation::Area => resize_area(img, new_h, new_w),
+    }
+}
+
+/// Blur an image and downsample it by 2.
+///
+/// A 5x5 Gaussian kernel with `sigma = 1` is applied with the `Replicate`
+/// border, then every second pixel is taken. The output shape is
+/// `((height + 1) / 2, (width + 1) / 2)`.
+pub fn pyr_down<S>(img: &ArrayBase<S, Ix2>) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    let blurred = gaussian_smooth_sigma(img, 5, 1.0, BorderType::Replicate);
+    blurred.slice(s![..;2, ..;2]).to_owned()
+}
+
+/// Build the Gaussian pyramid of an image.
+///
+/// The first level is the image itself and each following level is obtained by
+/// [pyr_down()](./fn.pyr_down.html). There are `levels` levels in total.
+pub fn gaussian_pyramid<S>(img: &ArrayBase<S, Ix2>, levels: usize) -> Vec<Array<f64, Ix2>>
+    where S: Data<Elem=f64>
+{
+    assert!(levels >= 1, "levels must be at least 1 in gaussian_pyramid().");
+    let mut pyramid = vec![img.to_owned()];
+    for _ in 1..levels {
+        let next = pyr_down(&pyramid[pyramid.len() - 1]);
+        pyramid.push(next);
+    }
+    pyramid
+}
+
+/// Build the Laplacian pyramid of an image.
+///
+/// Each level is the difference between a level of the Gaussian pyramid and the
+/// upsampled next level, while the last level is the last level of the Gaussian
+/// pyramid. Upsampling is done by [resize()](./fn.resize.html) with bilinear interpolation.
+/// The image is recovered exactly by
+/// [collapse_laplacian_pyramid()](./fn.collapse_laplacian_pyramid.html).
+pub fn laplacian_pyramid<S>(img: &ArrayBase<S, Ix2>, levels: usize) -> Vec<Array<f64, Ix2>>
+    where S: Data<Elem=f64>
+{
+    let gaussian = gaussian_pyramid(img, levels);
+    let mut pyramid = Vec::with_capacity(levels);
+    for k in 0..levels - 1 {
+        let shape = gaussian[k].shape();
+        let up = resize_bilinear(&gaussian[k + 1], shape[0], shape[1]);
+        pyramid.push(&gaussian[k] - &up);
+    }
+    pyramid.push(gaussian[levels - 1].clone());
+    pyramid
+}
+
+/// Reconstruct an image from its Laplacian pyramid.
+pub fn collapse_laplacian_pyramid(pyramid: &[Array<f64, Ix2>]) -> Array<f64, Ix2> {
+    assert!(!pyramid.is_empty(), "The pyramid must not be empty in collapse_laplacian_pyramid().");
+    let mut img = pyramid[pyramid.len() - 1].clone();
+    for level in pyramid.iter().rev().skip(1) {
+        let shape = level.shape();
+        img = resize_bilinear(&img, shape[0], shape[1]) + level;
+    }
+    img
+}
+
+/// Blend two Laplacian pyramids with a mask and reconstruct the result.
+///
+/// `mask` has the shape of the finest level, where 1.0 selects the first image
+/// and 0.0 selects the second one. The Gaussian pyramid of the mask weights
+/// each level, so low frequencies are blended over wide regions while details
+/// are blended over narrow regions, which hides the seam (multi-band blending).
+/// # Example:
+/// ```
+/// use simplecv::transform::{blend_pyramids, laplacian_pyramid};
+/// let black = ndarray::Array::zeros((32, 32));
+/// let white = ndarray::Array::ones((32, 32));
+/// let mask = ndarray::Array::from_shape_fn((32, 32), |(_, j)| if j < 16 { 1.0 } else { 0.0 });
+/// let blended = blend_pyramids(&laplacian_pyramid(&black, 4), &laplacian_pyramid(&white, 4), &mask);
+/// let row = blended.row(16);
+/// // the transition spreads over several columns instead of a hard seam
+/// assert!(row[13] > 0.05 && row[18] < 0.95);
+/// for j in 1..32 {
+///     assert!((row[j] - row[j - 1]).abs() < 0.4);
+/// }
+/// ```
+pub fn blend_pyramids<S>(a: &[Array<f64, Ix2>], b: &[Array<f64, Ix2>], mask: &ArrayBase<S, Ix2>) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    assert_eq!(a.len(), b.len(), "Pyramids must have the same number of levels in blend_pyramids().");
+    let mask_pyramid = gaussian_pyramid(mask, a.len());
+    let blended: Vec<Array<f64, Ix2>> = a.iter()
+        .zip(b.iter())
+        .zip(mask_pyramid.iter())
+        .map(|((la, lb), m)| la * m + lb * &m.mapv(|v| 1.0 - v))
+        .collect();
+    collapse_laplacian_pyramid(&blended)
+}
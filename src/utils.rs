@@ -1,7 +1,8 @@
 //! Collections of some util functions.
 //!
-use ndarray::{ArrayBase, Dimension, Data};
+use ndarray::{Array, ArrayBase, ArrayView1, ArrayView2, Axis, Dimension, Data, Ix1, Ix2, Ix3};
 use num::traits::Signed;
+use super::filter::{laplacian, BorderType};
 
 /// Compute the max absolute difference of two array.
 ///
@@ -25,6 +26,30 @@ pub fn max_diff<A, D, S>(a:&ArrayBase<S, D>, b:&ArrayBase<S, D>) -> A
      .map(|v| v.abs())
      .fold(A::zero(), |m, x| if m < x {x} else {m})
 }
+
+/// Compute the max absolute difference of two 2D arrays and its location.
+///
+/// The first location is returned when several pixels have the same max
+/// difference. It is useful for reporting where two images differ.
+/// # Example:
+/// ```
+/// let a = ndarray::Array::from_elem((3, 4), 0.5f64);
+/// let mut b = a.clone();
+/// b[[0, 1]] = 0.45;
+/// b[[2, 3]] = 0.8;
+/// let (diff, (i, j)) = simplecv::utils::max_diff_at(&a, &b);
+/// assert!((diff - 0.3).abs() < 1e-12);
+/// assert_eq!((i, j), (2, 3));
+/// ```
+pub fn max_diff_at<A, S, T>(a: &ArrayBase<S, Ix2>, b: &ArrayBase<T, Ix2>) -> (A, (usize, usize))
+    where A: Signed + PartialOrd + Copy, S: Data<Elem=A>, T: Data<Elem=A>
+{
+    assert_eq!(a.shape(), b.shape(), "Arrays must have the same shape in max_diff_at().");
+    a.indexed_iter()
+     .map(|(idx, v)| ((*v - b[idx]).abs(), idx))
+     .fold((A::zero(), (0, 0)), |m, x| if m.0 < x.0 {x} else {m})
+}
+
 /// Transform a float to u8 and multiply it by 255.
 /// 
 /// It is used in transform `image` output to f64 array used by `simplecv`.
@@ -32,7 +57,43 @@ pub fn max_diff<A, D, S>(a:&ArrayBase<S, D>, b:&ArrayBase<S, D>) -> A
 /// assert_eq!(simplecv::utils::f2u(0.9), 230);
 /// ```
 pub fn f2u(v: f64) -> u8 {
-    (v * 255.0 + 0.5) as u8
+    f2u_round(v, RoundMode::Nearest)
+}
+
+/// Rounding modes for converting floats to u8.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RoundMode {
+    /// Round half up, which is the same as `f2u`.
+    Nearest,
+    /// Round towards negative infinity.
+    Floor,
+    /// Round towards positive infinity.
+    Ceil,
+}
+
+/// Transform a float to u8 and multiply it by 255 with the given rounding mode.
+///
+/// Results out of \[0, 255\] are saturated.
+/// ```
+/// use simplecv::utils::{f2u_round, RoundMode};
+/// let half = 0.5 / 255.0;
+/// assert_eq!(f2u_round(half, RoundMode::Nearest), 1);
+/// assert_eq!(f2u_round(half, RoundMode::Floor), 0);
+/// assert_eq!(f2u_round(half, RoundMode::Ceil), 1);
+/// let below = 0.49 / 255.0;
+/// assert_eq!(f2u_round(below, RoundMode::Nearest), 0);
+/// assert_eq!(f2u_round(below, RoundMode::Floor), 0);
+/// assert_eq!(f2u_round(below, RoundMode::Ceil), 1);
+/// assert_eq!(f2u_round(1.0, RoundMode::Ceil), 255);
+/// assert_eq!(f2u_round(0.9, RoundMode::Nearest), simplecv::utils::f2u(0.9));
+/// ```
+pub fn f2u_round(v: f64, mode: RoundMode) -> u8 {
+    let scaled = v * 255.0;
+    match mode {
+        RoundMode::Nearest => (scaled + 0.5) as u8,
+        RoundMode::Floor => scaled.floor() as u8,
+        RoundMode::Ceil => scaled.ceil() as u8,
+    }
 }
 
 /// max of two value
@@ -41,3 +102,303 @@ pub fn max<A>(a: A, b:A) -> A
 {
     if a > b {a} else {b}
 }
+
+/// Compute the variance of the Laplacian response of an image.
+///
+/// It is a common focus measure: the sharper the image, the larger the value.
+/// For example, it can be used to select the sharpest frame from a burst.
+/// # Example:
+/// ```
+/// use simplecv::filter::*;
+/// let board = ndarray::Array::from_shape_fn((16, 16), |(i, j)| ((i / 4 + j / 4) % 2) as f64);
+/// let blurred = gaussian_smooth(&board, 5, BorderType::Reflect);
+/// let sharp_score = simplecv::utils::variance_of_laplacian(&board, BorderType::Reflect);
+/// let blur_score = simplecv::utils::variance_of_laplacian(&blurred, BorderType::Reflect);
+/// assert!(sharp_score > blur_score);
+/// ```
+pub fn variance_of_laplacian<S>(img: &ArrayBase<S, Ix2>, border: BorderType) -> f64
+    where S: Data<Elem=f64>
+{
+    let response = laplacian(img, border);
+    let n = response.len() as f64;
+    let mean = response.sum() / n;
+    response.fold(0.0, |acc, x| acc + (x - mean).powi(2)) / n
+}
+
+/// Iterate over all pixels of an image.
+///
+/// Each item is the location `(i, j)` of the pixel and a view of its channels.
+/// # Example:
+/// ```
+/// let img = ndarray::Array::from_shape_fn((2, 2, 3), |(i, j, c)| (i * 6 + j * 3 + c) as f64);
+/// let pixels: Vec<_> = simplecv::utils::pixels(&img)
+///     .map(|(loc, p)| (loc, p.to_vec()))
+///     .collect();
+/// assert_eq!(pixels, vec![((0, 0), vec![0.0, 1.0, 2.0]),
+///                         ((0, 1), vec![3.0, 4.0, 5.0]),
+///                         ((1, 0), vec![6.0, 7.0, 8.0]),
+///                         ((1, 1), vec![9.0, 10.0, 11.0])]);
+/// ```
+pub fn pixels<'a, A>(img: &'a ArrayBase<A, Ix3>) -> impl Iterator<Item=((usize, usize), ArrayView1<'a, f64>)> + 'a
+    where A: Data<Elem=f64>
+{
+    let h = img.shape()[0];
+    let w = img.shape()[1];
+    (0..h).flat_map(move |i| (0..w).map(move |j| ((i, j), img.slice(s![i, j, ..]))))
+}
+
+/// Apply a 2D operation to each channel of an image independently.
+///
+/// The results of all channels are stacked into a new image. `f` must produce
+/// outputs of the same shape for all channels.
+/// # Example:
+/// ```
+/// use simplecv::color::histeq;
+/// let img = ndarray::Array::from_shape_fn((4, 4, 3), |(i, j, c)| (i * 4 + j) as f64 / (30.0 + c as f64));
+/// let equalized = simplecv::utils::map_channels(&img, |channel| histeq(channel));
+/// assert_eq!(equalized.shape(), &[4, 4, 3]);
+/// for c in 0..3 {
+///     let expected = histeq(&img.index_axis(ndarray::Axis(2), c));
+///     assert_eq!(equalized.index_axis(ndarray::Axis(2), c), expected);
+/// }
+/// ```
+pub fn map_channels<A, F>(img: &ArrayBase<A, Ix3>, f: F) -> Array<f64, Ix3>
+    where A: Data<Elem=f64>, F: Fn(&ArrayView2<f64>) -> Array<f64, Ix2>
+{
+    let results: Vec<Array<f64, Ix2>> = img.axis_iter(Axis(2))
+                                           .map(|channel| f(&channel))
+                                           .collect();
+    let views: Vec<_> = results.iter()
+                               .map(|r| r.view().insert_axis(Axis(2)))
+                               .collect();
+    ndarray::stack(Axis(2), &views).unwrap()
+}
+
+/// Compute the mean of each row of an image.
+///
+/// It is the horizontal projection profile, which can be used to find text
+/// lines in document images.
+/// # Example:
+/// ```
+/// // stripes on rows 1 and 3
+/// let img = ndarray::Array::from_shape_fn((5, 4), |(i, _)| (i % 2) as f64);
+/// let profile = simplecv::utils::row_means(&img);
+/// assert_eq!(profile, ndarray::arr1(&[0.0, 1.0, 0.0, 1.0, 0.0]));
+/// ```
+pub fn row_means<S>(img: &ArrayBase<S, Ix2>) -> Array<f64, Ix1>
+    where S: Data<Elem=f64>
+{
+    img.mean_axis(Axis(1))
+}
+
+/// Compute the mean of each column of an image.
+///
+/// It is the vertical projection profile.
+/// # Example:
+/// ```
+/// // stripes on columns 0 and 2
+/// let img = ndarray::Array::from_shape_fn((4, 3), |(_, j)| ((j + 1) % 2) as f64);
+/// let profile = simplecv::utils::col_means(&img);
+/// assert_eq!(profile, ndarray::arr1(&[1.0, 0.0, 1.0]));
+/// ```
+pub fn col_means<S>(img: &ArrayBase<S, Ix2>) -> Array<f64, Ix1>
+    where S: Data<Elem=f64>
+{
+    img.mean_axis(Axis(0))
+}
+
+/// Detect text lines in a binary document image by the horizontal projection.
+///
+/// Rows whose mean (the foreground density for a 0/1 image) exceeds `min_density`
+/// are foreground rows, so sparse noise does not start a line. Runs of foreground
+/// rows separated by fewer than `min_gap` background rows are merged.
+/// Return the row ranges `(start, end)` of the lines, where `end` is exclusive.
+/// # Example:
+/// ```
+/// let mut page = ndarray::Array::zeros((12, 8));
+/// for j in 1..7 {
+///     page[[2, j]] = 1.0;
+///     page[[4, j]] = 1.0;
+///     page[[8, j]] = 1.0;
+///     page[[9, j]] = 1.0;
+/// }
+/// // a stray pixel is not a line
+/// page[[6, 3]] = 1.0;
+/// let lines = simplecv::utils::detect_text_lines(&page, 2, 0.2);
+/// assert_eq!(lines, vec![(2, 5), (8, 10)]);
+/// let lines = simplecv::utils::detect_text_lines(&page, 2, 0.0);
+/// assert_eq!(lines, vec![(2, 10)]);
+/// ```
+pub fn detect_text_lines<S>(binary: &ArrayBase<S, Ix2>, min_gap: usize, min_density: f64) -> Vec<(usize, usize)>
+    where S: Data<Elem=f64>
+{
+    let profile = row_means(binary);
+    let mut lines: Vec<(usize, usize)> = Vec::new();
+    for (i, density) in profile.iter().enumerate() {
+        if *density <= min_density {
+            continue;
+        }
+        match lines.last_mut() {
+            Some(line) if i - line.1 < min_gap => line.1 = i + 1,
+            _ => lines.push((i, i + 1))
+        }
+    }
+    lines
+}
+
+/// Mark the pixels where two images differ by more than `tol`.
+///
+/// Return the mask and the number of differing pixels. It helps to localize
+/// where two results diverge, while `max_diff` only gives a scalar.
+/// # Example:
+/// ```
+/// let a = ndarray::Array::from_elem((3, 4), 0.5f64);
+/// let mut b = a.clone();
+/// b[[1, 2]] = 0.6;
+/// b[[2, 3]] = 0.5001;
+/// let (mask, count) = simplecv::utils::diff_mask(&a, &b, 1e-3);
+/// assert_eq!(count, 1);
+/// assert!(mask[[1, 2]]);
+/// assert_eq!(mask.iter().filter(|m| **m).count(), 1);
+/// ```
+pub fn diff_mask<S, T>(a: &ArrayBase<S, Ix2>, b: &ArrayBase<T, Ix2>, tol: f64) -> (Array<bool, Ix2>, usize)
+    where S: Data<Elem=f64>, T: Data<Elem=f64>
+{
+    assert_eq!(a.shape(), b.shape(), "Images must have the same shape in diff_mask().");
+    let mut mask = Array::from_elem((a.shape()[0], a.shape()[1]), false);
+    let mut count = 0;
+    for ((m, x), y) in mask.iter_mut().zip(a.iter()).zip(b.iter()) {
+        if (x - y).abs() > tol {
+            *m = true;
+            count += 1;
+        }
+    }
+    (mask, count)
+}
+
+/// Compute the summed-area table of values mapped by `f`.
+fn summed_area_table<S, F>(src: &ArrayBase<S, Ix2>, f: F) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>, F: Fn(f64) -> f64
+{
+    let height = src.shape()[0];
+    let width = src.shape()[1];
+    let mut table = Array::zeros((height + 1, width + 1));
+    for i in 0..height {
+        for j in 0..width {
+            table[[i + 1, j + 1]] = f(src[[i, j]]) + table[[i, j + 1]] + table[[i + 1, j]] - table[[i, j]];
+        }
+    }
+    table
+}
+
+/// Compute the integral image (summed-area table) of an image.
+///
+/// The output has the shape `(height + 1, width + 1)`, where `out[[i, j]]` is the
+/// sum of `src[[0..i, 0..j]]`. The sum over any rectangle `[x0, x1) x [y0, y1)`
+/// is `out[[x1, y1]] - out[[x0, y1]] - out[[x1, y0]] + out[[x0, y0]]`.
+/// # Example:
+/// ```
+/// let img = ndarray::arr2(&[[1.0, 2.0], [3.0, 4.0]]);
+/// let integral = simplecv::utils::integral_image(&img);
+/// assert_eq!(integral, ndarray::arr2(&[[0.0, 0.0, 0.0], [0.0, 1.0, 3.0], [0.0, 4.0, 10.0]]));
+/// ```
+pub fn integral_image<S>(src: &ArrayBase<S, Ix2>) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    summed_area_table(src, |v| v)
+}
+
+/// Compute the integral image of the squared values of an image.
+///
+/// The layout is the same as [integral_image()](./fn.integral_image.html). With
+/// both tables, the variance of any window costs O(1).
+/// # Example:
+/// ```
+/// use simplecv::utils::{integral_image, integral_image_sq};
+/// let img = ndarray::Array::from_shape_fn((6, 7), |(i, j)| ((i * 5 + j * 3) % 7) as f64 / 7.0);
+/// let sum = integral_image(&img);
+/// let sqsum = integral_image_sq(&img);
+/// // the window [1, 4) x [2, 6)
+/// let rect = |t: &ndarray::Array2<f64>| t[[4, 6]] - t[[1, 6]] - t[[4, 2]] + t[[1, 2]];
+/// let n = 12.0;
+/// let mean = rect(&sum) / n;
+/// let variance = rect(&sqsum) / n - mean * mean;
+/// use ndarray::s;
+/// let window = img.slice(s![1..4, 2..6]);
+/// let direct_mean = window.sum() / n;
+/// let direct = window.mapv(|v| (v - direct_mean).powi(2)).sum() / n;
+/// assert!((variance - direct).abs() < 1e-9);
+/// ```
+pub fn integral_image_sq<S>(src: &ArrayBase<S, Ix2>) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>
+{
+    summed_area_table(src, |v| v * v)
+}
+
+/// Apply a 2D operation on an image tile by tile.
+///
+/// The image is split into `tile_size` x `tile_size` tiles. Each tile is
+/// extended by `overlap` pixels on every side (within the image) before
+/// `op` is applied, and only the center part of the result is written to the
+/// output, so that the border artifacts of `op` are discarded. `op` must
+/// return an array of the same shape as its input. When `overlap` is not
+/// smaller than the radius of `op`, the result is the same as applying `op`
+/// on the whole image.
+/// # Example:
+/// ```
+/// use simplecv::filter::{gaussian_smooth, BorderType};
+/// let img = ndarray::Array::from_shape_fn((40, 50), |(i, j)| ((i * 7 + j * 13) % 17) as f64 / 17.0);
+/// let whole = gaussian_smooth(&img, 5, BorderType::Replicate);
+/// let tiled = simplecv::utils::process_tiles(&img, 16, 2, |tile| gaussian_smooth(tile, 5, BorderType::Replicate));
+/// use ndarray::s;
+/// let diff = simplecv::utils::max_diff(&whole.slice(s![2..38, 2..48]).to_owned(),
+///                                      &tiled.slice(s![2..38, 2..48]).to_owned());
+/// assert!(diff < 1e-12);
+/// ```
+pub fn process_tiles<S, F>(img: &ArrayBase<S, Ix2>, tile_size: usize, overlap: usize, op: F) -> Array<f64, Ix2>
+    where S: Data<Elem=f64>, F: Fn(&ArrayView2<f64>) -> Array<f64, Ix2>
+{
+    assert!(tile_size > 0, "tile_size must be positive in process_tiles().");
+    let height = img.shape()[0];
+    let width = img.shape()[1];
+    let mut out = Array::zeros((height, width));
+    for r0 in (0..height).step_by(tile_size) {
+        for c0 in (0..width).step_by(tile_size) {
+            let r1 = usize::min(r0 + tile_size, height);
+            let c1 = usize::min(c0 + tile_size, width);
+            let er0 = r0.saturating_sub(overlap);
+            let ec0 = c0.saturating_sub(overlap);
+            let er1 = usize::min(r1 + overlap, height);
+            let ec1 = usize::min(c1 + overlap, width);
+            let result = op(&img.slice(s![er0..er1, ec0..ec1]));
+            assert_eq!(result.shape(), &[er1 - er0, ec1 - ec0], "op must keep the shape of tiles in process_tiles().");
+            out.slice_mut(s![r0..r1, c0..c1])
+               .assign(&result.slice(s![r0 - er0..r1 - er0, c0 - ec0..c1 - ec0]));
+        }
+    }
+    out
+}
+
+/// Linearly rescale an array so that its min and max become `new_min` and `new_max`.
+///
+/// A constant array is filled with `new_min`.
+/// # Example:
+/// ```
+/// let a = ndarray::arr2(&[[2.0, 4.0], [6.0, 10.0]]);
+/// let rescaled = simplecv::utils::normalize_range(&a, -1.0, 1.0);
+/// assert_eq!(rescaled, ndarray::arr2(&[[-1.0, -0.5], [0.0, 1.0]]));
+/// let constant = simplecv::utils::normalize_range(&ndarray::Array::from_elem((2, 2), 0.3), 0.0, 1.0);
+/// assert_eq!(constant, ndarray::Array::zeros((2, 2)));
+/// ```
+pub fn normalize_range<S, D>(img: &ArrayBase<S, D>, new_min: f64, new_max: f64) -> Array<f64, D>
+    where S: Data<Elem=f64>, D: Dimension
+{
+    let min = img.fold(f64::INFINITY, |m, &v| m.min(v));
+    let max = img.fold(f64::NEG_INFINITY, |m, &v| m.max(v));
+    if max > min {
+        let scale = (new_max - new_min) / (max - min);
+        img.mapv(|v| (v - min) * scale + new_min)
+    } else {
+        img.mapv(|_| new_min)
+    }
+}